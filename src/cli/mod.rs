@@ -0,0 +1,643 @@
+use std::io::Write;
+
+use crate::core::{entity, vault};
+
+/// Athena, a personal knowledge base.
+#[derive(clap::Parser)]
+#[command(name = "athena", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Start the web interface (the default)
+    Serve {
+        /// Address to listen on, overrides the configuration file
+        #[arg(long)]
+        bind: Option<String>,
+    },
+    /// List all entities with their titles
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print an entity as plain text
+    Show {
+        id: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search the vault
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Maximum number of results
+        #[arg(long, short = 'n')]
+        limit: Option<usize>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// List all tags, or the entities carrying a tag
+    Tags {
+        tag: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the outgoing links and backlinks of an entity
+    Links {
+        id: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Maintain the cache directory
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Dump all entities as JSON
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Import .eml files, mbox archives and Maildirs into the email volume
+    Import {
+        #[arg(required = true)]
+        paths: Vec<std::path::PathBuf>,
+        /// Store the emails gzip-compressed
+        #[arg(long)]
+        gzip: bool,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum CacheAction {
+    /// Throw away the cache and rebuild it from scratch
+    Rebuild,
+    /// Remove cache entries of files that no longer exist
+    Gc,
+    /// Show how much is cached
+    Stats {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Cli {
+    pub fn parse() -> Self {
+        <Self as clap::Parser>::parse()
+    }
+
+    /// The address given on the command line, if the web interface is started.
+    pub fn bind_address(&self) -> Option<&str> {
+        match &self.command {
+            Some(Command::Serve { bind }) => bind.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_serve(&self) -> bool {
+        matches!(self.command, None | Some(Command::Serve { .. }))
+    }
+
+    /// Run everything except `serve`. Returns `Err` if the command failed, the reason has already been printed.
+    pub fn run(self, vault: &vault::Vault) -> Result<(), ()> {
+        let result = match self.command {
+            None | Some(Command::Serve { .. }) => Ok(()),
+            Some(Command::List { json }) => list(vault, json),
+            Some(Command::Show { id, json }) => show(vault, &id, json),
+            Some(Command::Search { query, limit, json }) => search(vault, &query.join(" "), limit, json),
+            Some(Command::Tags { tag, json }) => tags(vault, tag.as_deref(), json),
+            Some(Command::Links { id, json }) => links(vault, &id, json),
+            Some(Command::Cache { action }) => cache(vault, action),
+            Some(Command::Export { output }) => export(vault, output.as_deref()),
+            Some(Command::Import { paths, gzip }) => import(vault, &paths, gzip),
+        };
+
+        vault.save_cache();
+
+        result
+    }
+}
+
+fn parse_id(vault: &vault::Vault, id: &str) -> Result<entity::Id, ()> {
+    let parsed = entity::Id::with_id(id).map_err(|_| {
+        eprintln!("Invalid ID: {}", id);
+    })?;
+
+    vault.canonical_id(&parsed).ok_or_else(|| {
+        eprintln!("No such entity: {}", id);
+    })
+}
+
+fn title_of(vault: &vault::Vault, id: &entity::Id) -> String {
+    vault
+        .title_of_entity(id)
+        .unwrap_or_else(|| id.as_readable_string())
+}
+
+/// Write the output of a command to stdout. The reading end of a pipe may go away early,
+/// e.g. in `athena list | head`, that is not an error.
+fn write_output<F>(write: F) -> Result<(), ()>
+where
+    F: FnOnce(&mut std::io::StdoutLock) -> std::io::Result<()>,
+{
+    let mut stdout = std::io::stdout().lock();
+
+    write(&mut stdout)
+        .and_then(|_| stdout.flush())
+        .or_else(|e| match e.kind() {
+            std::io::ErrorKind::BrokenPipe => Ok(()),
+            _ => Err(e),
+        })
+        .map_err(|e| {
+            eprintln!("Unable to write output: {}", e);
+        })
+}
+
+fn print_json(value: &serde_json::Value) -> Result<(), ()> {
+    write_output(|out| {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+        writeln!(out)
+    })
+}
+
+fn type_of_entity(entity: &entity::Entity) -> &'static str {
+    match entity {
+        entity::Entity::File(_) => "file",
+        entity::Entity::Zettel(_) => "zettel",
+        entity::Entity::Email(_) => "email",
+    }
+}
+
+fn render_block(block: &entity::zettel::document::block::Block, prefix: &str, out: &mut String) {
+    use entity::zettel::document::{block::Block, node::nodes_to_plain_text};
+
+    let text = match block {
+        Block::Heading(heading) => format!("{} {}", "#".repeat(heading.level as usize), block.to_plain_text().trim()),
+        Block::Line => "---".to_string(),
+        Block::CodeBlock(code_block) => format!(
+            "```{}\n{}\n```",
+            code_block.language.as_deref().unwrap_or(""),
+            code_block.code.trim_end()
+        ),
+        Block::Callout(callout) => {
+            let nested = format!("{}> ", prefix);
+
+            if callout.kind.name().is_some() {
+                let title = match nodes_to_plain_text(&callout.title).trim() {
+                    "" => callout.kind.default_title(),
+                    title => title.to_string(),
+                };
+
+                out.push_str(&format!("{}{}\n", nested, title));
+            }
+
+            for (index, block) in callout.blocks.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(nested.trim_end());
+                    out.push('\n');
+                }
+                render_block(block, &nested, out);
+            }
+
+            return;
+        }
+        Block::List(list) => render_list(list),
+        Block::Table(table) => table
+            .all_rows()
+            .map(|row| {
+                let cells = row.iter().map(|cell| nodes_to_plain_text(cell).trim().to_string());
+                format!("| {} |", cells.collect::<Vec<_>>().join(" | "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Block::Paragraph(_) => block.to_plain_text().trim().to_string(),
+    };
+
+    for line in text.lines() {
+        out.push_str(prefix);
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn render_list(list: &entity::zettel::document::block::list::List) -> String {
+    use entity::zettel::document::{block::list::Kind, node::nodes_to_plain_text};
+
+    let indent = "  ".repeat(list.depth);
+    let mut lines = Vec::new();
+
+    for (index, bullet_point) in list.items.iter().enumerate() {
+        let marker = match list.kind {
+            Kind::Unordered => "-".to_string(),
+            Kind::Ordered { start } => format!("{}.", start + index as u64),
+        };
+
+        let checkbox = match &bullet_point.task_info {
+            Some(task_info) => format!("[{}] ", task_info.status.as_char()),
+            None => String::new(),
+        };
+
+        lines.push(format!("{}{} {}{}", indent, marker, checkbox, nodes_to_plain_text(&bullet_point.nodes).trim()));
+
+        for child in &bullet_point.children {
+            lines.push(render_list(child));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn render_zettel(zettel: &entity::zettel::Zettel) -> String {
+    let mut out = String::new();
+
+    if let Some(document) = zettel.body_as_document() {
+        for (index, block) in document.blocks().iter().enumerate() {
+            if index > 0 {
+                out.push('\n');
+            }
+
+            render_block(block, "", &mut out);
+        }
+    }
+
+    out
+}
+
+fn render_email_headers(email: &entity::email::Email) -> Vec<(&'static str, String)> {
+    let join = |addresses: &[entity::email::Address]| {
+        addresses
+            .iter()
+            .map(entity::email::Address::display)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let headers = &email.headers;
+
+    vec![
+        ("From", join(&headers.from)),
+        ("To", join(&headers.to)),
+        ("Cc", join(&headers.cc)),
+        ("Subject", headers.subject.clone().unwrap_or_default()),
+        ("Date", headers.date.map(|date| date.to_rfc2822()).unwrap_or_default()),
+    ]
+    .into_iter()
+    .filter(|(_, value)| !value.is_empty())
+    .collect()
+}
+
+/// The text of an entity, as it is printed by `show` and `export`.
+fn text_of_entity(entity: &entity::Entity) -> Option<String> {
+    match entity {
+        entity::Entity::File(file) => file.content_as_string(),
+        entity::Entity::Zettel(zettel) => Some(render_zettel(zettel)),
+        entity::Entity::Email(email) => email.text(),
+    }
+}
+
+fn list(vault: &vault::Vault, json: bool) -> Result<(), ()> {
+    let mut entities = vault
+        .list_entities()
+        .into_iter()
+        .map(|id| {
+            let title = title_of(vault, &id);
+            (id, title)
+        })
+        .collect::<Vec<_>>();
+
+    entities.sort_by(|a, b| a.1.cmp(&b.1));
+
+    if json {
+        return print_json(&serde_json::json!(entities
+            .iter()
+            .map(|(id, title)| serde_json::json!({ "id": id, "title": title }))
+            .collect::<Vec<_>>()));
+    }
+
+    write_output(|out| {
+        for (id, title) in entities {
+            writeln!(out, "{}\t{}", id.id(), title)?;
+        }
+
+        Ok(())
+    })
+}
+
+fn show(vault: &vault::Vault, id: &str, json: bool) -> Result<(), ()> {
+    let parsed = entity::Id::with_id(id).map_err(|_| {
+        eprintln!("Invalid ID: {}", id);
+    })?;
+
+    // Attachments are not listed, so they have no canonical ID of their own
+    let id = match parsed {
+        entity::Id::Attachment(..) => parsed,
+        _ => parse_id(vault, id)?,
+    };
+
+    let entity = vault.load_entity(&id).ok_or_else(|| {
+        eprintln!("Unable to load entity: {}", id.id());
+    })?;
+
+    let title = title_of(vault, &id);
+    let text = text_of_entity(&entity);
+
+    if json {
+        let mut value = serde_json::json!({
+            "id": id,
+            "title": title,
+            "type": type_of_entity(&entity),
+            "tags": vault.tags_of_entity(&id),
+            "text": text,
+        });
+
+        if let entity::Entity::Email(email) = &entity {
+            value["headers"] = render_email_headers(email)
+                .into_iter()
+                .map(|(name, value)| (name.to_lowercase(), serde_json::Value::String(value)))
+                .collect();
+            value["attachments"] = email
+                .attachments()
+                .iter()
+                .enumerate()
+                .map(|(index, attachment)| {
+                    serde_json::json!({
+                        "id": id.attachment(index),
+                        "name": attachment.name,
+                        "mime_type": attachment.mime_type,
+                    })
+                })
+                .collect();
+        }
+
+        return print_json(&value);
+    }
+
+    write_output(|out| {
+        match &entity {
+            entity::Entity::Email(email) => {
+                for (name, value) in render_email_headers(email) {
+                    writeln!(out, "{}: {}", name, value)?;
+                }
+
+                for (index, attachment) in email.attachments().iter().enumerate() {
+                    writeln!(
+                        out,
+                        "Attachment: {} ({})",
+                        attachment.name.as_deref().unwrap_or("unnamed"),
+                        id.attachment(index).id()
+                    )?;
+                }
+
+                writeln!(out)?;
+            }
+            _ => {
+                writeln!(out, "{}", title)?;
+                writeln!(out, "{}", "=".repeat(title.chars().count()))?;
+                writeln!(out)?;
+            }
+        }
+
+        match text {
+            Some(text) => write!(out, "{}", text),
+            None => match &entity {
+                entity::Entity::File(file) => {
+                    writeln!(out, "[{}, {} bytes]", file.metadata().mime_type(), file.content().len())
+                }
+                _ => Ok(()),
+            },
+        }
+    })
+}
+
+fn search(vault: &vault::Vault, query: &str, limit: Option<usize>, json: bool) -> Result<(), ()> {
+    let mut hits = vault.search(query);
+
+    if let Some(limit) = limit {
+        hits.truncate(limit);
+    }
+
+    if json {
+        return print_json(&serde_json::json!(hits));
+    }
+
+    write_output(|out| {
+        for hit in hits {
+            if hit.aliases.is_empty() {
+                writeln!(out, "{}\t{}", hit.id.id(), hit.title)?;
+            } else {
+                writeln!(out, "{}\t{} ({})", hit.id.id(), hit.title, hit.aliases.join(", "))?;
+            }
+
+            if let Some(snippet) = hit.snippet {
+                writeln!(out, "\t{}", snippet.text)?;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+fn tags(vault: &vault::Vault, tag: Option<&str>, json: bool) -> Result<(), ()> {
+    match tag {
+        Some(tag) => {
+            let tag = tag.trim_start_matches('#');
+
+            let entities = vault
+                .entities_with_tag(tag)
+                .into_iter()
+                .map(|id| {
+                    let title = title_of(vault, &id);
+                    (id, title)
+                })
+                .collect::<Vec<_>>();
+
+            if json {
+                return print_json(&serde_json::json!(entities
+                    .iter()
+                    .map(|(id, title)| serde_json::json!({ "id": id, "title": title }))
+                    .collect::<Vec<_>>()));
+            }
+
+            write_output(|out| {
+                for (id, title) in entities {
+                    writeln!(out, "{}\t{}", id.id(), title)?;
+                }
+
+                Ok(())
+            })
+        }
+        None => {
+            let tags = vault.tags();
+
+            if json {
+                return print_json(&serde_json::json!(tags
+                    .iter()
+                    .map(|(tag, count)| serde_json::json!({ "tag": tag, "count": count }))
+                    .collect::<Vec<_>>()));
+            }
+
+            write_output(|out| {
+                for (tag, count) in tags {
+                    writeln!(out, "{}\t{}", tag, count)?;
+                }
+
+                Ok(())
+            })
+        }
+    }
+}
+
+fn links(vault: &vault::Vault, id: &str, json: bool) -> Result<(), ()> {
+    let id = parse_id(vault, id)?;
+
+    let mut outgoing = Vec::new();
+
+    for (target, _) in vault.outgoing_links(&id) {
+        if !outgoing.contains(&target) {
+            outgoing.push(target);
+        }
+    }
+
+    let backlinks = vault.backlinks(&id);
+
+    if json {
+        return print_json(&serde_json::json!({
+            "id": id,
+            "outgoing": outgoing
+                .iter()
+                .map(|target| serde_json::json!({ "id": target, "title": title_of(vault, target) }))
+                .collect::<Vec<_>>(),
+            "backlinks": backlinks
+                .iter()
+                .map(|backlink| serde_json::json!({
+                    "id": backlink.source,
+                    "title": title_of(vault, &backlink.source),
+                    "contexts": backlink.contexts,
+                }))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    write_output(|out| {
+        for target in outgoing {
+            writeln!(out, "->\t{}\t{}", target.id(), title_of(vault, &target))?;
+        }
+
+        for backlink in backlinks {
+            writeln!(out, "<-\t{}\t{}", backlink.source.id(), title_of(vault, &backlink.source))?;
+        }
+
+        Ok(())
+    })
+}
+
+fn cache(vault: &vault::Vault, action: CacheAction) -> Result<(), ()> {
+    match action {
+        CacheAction::Rebuild => {
+            vault.rebuild_cache().map_err(|e| {
+                eprintln!("Unable to rebuild the cache: {}", e);
+            })?;
+
+            write_output(|out| writeln!(out, "Cache rebuilt"))?;
+        }
+        CacheAction::Gc => {
+            let removed = vault.collect_garbage();
+
+            write_output(|out| writeln!(out, "Removed {} stale cache entries", removed))?;
+        }
+        CacheAction::Stats { json } => {
+            let stats = vault.cache_stats().ok_or_else(|| {
+                eprintln!("Unable to access the cache");
+            })?;
+
+            if json {
+                return print_json(&serde_json::json!(stats));
+            }
+
+            write_output(|out| {
+                for (name, storage) in [("by_id", stats.by_id), ("by_path", stats.by_path), ("by_sha256", stats.by_sha256)] {
+                    writeln!(out, "{}\t{} entries\t{} bytes", name, storage.entries, storage.bytes)?;
+                }
+
+                writeln!(out, "search\t{} documents\t{} terms", stats.search_documents, stats.search_terms)
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn export(vault: &vault::Vault, output: Option<&std::path::Path>) -> Result<(), ()> {
+    let mut entities = Vec::new();
+
+    for id in vault.list_entities() {
+        let entity = match vault.load_entity(&id) {
+            Some(entity) => entity,
+            None => continue,
+        };
+
+        let links = vault
+            .outgoing_links(&id)
+            .into_iter()
+            .map(|(target, _)| target)
+            .collect::<Vec<_>>();
+
+        entities.push(serde_json::json!({
+            "id": id,
+            "title": title_of(vault, &id),
+            "type": type_of_entity(&entity),
+            "tags": vault.tags_of_entity(&id),
+            "links": links,
+            "text": text_of_entity(&entity),
+        }));
+    }
+
+    let count = entities.len();
+    let value = serde_json::json!(entities);
+
+    match output {
+        Some(path) => {
+            let file = std::fs::File::create(path).map_err(|e| {
+                eprintln!("Unable to create {:?}: {}", path, e);
+            })?;
+
+            serde_json::to_writer_pretty(std::io::BufWriter::new(file), &value).map_err(|e| {
+                eprintln!("Unable to write {:?}: {}", path, e);
+            })?;
+
+            eprintln!("Exported {} entities to {:?}", count, path);
+
+            Ok(())
+        }
+        None => print_json(&value),
+    }
+}
+
+fn import(vault: &vault::Vault, paths: &[std::path::PathBuf], gzip: bool) -> Result<(), ()> {
+    let options = vault::import::ImportOptions { compress: gzip };
+
+    let mut failed = false;
+
+    for path in paths {
+        let report = vault.import_emails(path, &options);
+
+        for (source, reason) in &report.failed {
+            eprintln!("Failed to import {}: {}", source, reason);
+        }
+
+        failed |= !report.failed.is_empty();
+
+        // The remaining paths are still imported if the output can't be written
+        failed |= write_output(|out| writeln!(out, "{}: {}", path.display(), report.summary())).is_err();
+    }
+
+    if failed {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
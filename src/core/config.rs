@@ -2,16 +2,124 @@ use super::vault;
 use dirs;
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeKind {
+    Directory,
+    Email,
+}
+
+#[derive(Debug, Clone)]
+pub struct VolumeConfig {
+    pub kind: VolumeKind,
+    pub path: std::path::PathBuf,
+    pub flags: vault::volume::flags::Flags,
+}
+
+impl VolumeConfig {
+    pub fn new(kind: VolumeKind, path: std::path::PathBuf, flags: vault::volume::flags::Flags) -> Self {
+        Self { kind, path, flags }
+    }
+}
+
+/// A volume as it is written down in the configuration file.
+#[derive(serde::Deserialize)]
+struct VolumeEntry {
+    kind: VolumeKind,
+    path: String,
+    zettels: Option<bool>,
+    #[serde(default)]
+    read_only: bool,
+    #[serde(default)]
+    hidden: bool,
+}
+
+/// The configuration file, usually `~/.athena/config.toml`:
+///
+/// ```toml
+/// cache_path = "~/.athena"
+/// bind_address = "127.0.0.1:8080"
+///
+/// [[volumes]]
+/// kind = "directory"
+/// path = "~/Vaults/Obsidian"
+///
+/// [[volumes]]
+/// kind = "email"
+/// path = "~/Mail/athena"
+///
+/// [[volumes]]
+/// kind = "directory"
+/// path = "~/Archive"
+/// zettels = false
+/// hidden = true
+/// ```
+#[derive(serde::Deserialize)]
+struct ConfigFile {
+    cache_path: Option<String>,
+    bind_address: Option<String>,
+    #[serde(default)]
+    volumes: Vec<VolumeEntry>,
+}
+
+/// Expand `~` to the home directory. Relative paths are taken relative to `base`.
+fn resolve_path(path: &str, base: &std::path::Path) -> std::path::PathBuf {
+    if path == "~" {
+        return dirs::home_dir().unwrap_or_default();
+    }
+
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    base.join(path)
+}
+
+fn default_config_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("ATHENA_CONFIG")
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".athena").join("config.toml")))
+}
+
+fn suggest_vault_path() -> Option<std::path::PathBuf> {
+    // Suggest a vault path by looking at the environment variable ATHENA_VAULT_PATH.
+    // If that does not exist, try to find ~/Vaults/Obsidian
+    // If that does not exist, refer to the default path ./example
+    // If none of these exist, return None
+
+    fn try_vault_path<T: Into<std::path::PathBuf>>(path: T) -> Option<std::path::PathBuf> {
+        let path = path.into();
+        if path.exists() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    let home_dir = std::env::var("HOME").ok();
+
+    std::env::var("ATHENA_VAULT_PATH")
+        .ok()
+        .and_then(try_vault_path)
+        .or_else(|| home_dir.and_then(|home_dir| try_vault_path(format!("{}/Vaults/Obsidian", home_dir))))
+        .or_else(|| try_vault_path("./example"))
+}
+
+
 pub(crate) struct Config {
     pub cache_path: Option<std::path::PathBuf>,
-    pub vault_path: Option<std::path::PathBuf>,
+    pub bind_address: Option<String>,
+    pub volumes: Vec<VolumeConfig>,
 }
 
 impl Config {
     pub fn new() -> Self {
         Self {
             cache_path: None,
-            vault_path: None
+            bind_address: None,
+            volumes: Vec::new(),
         }
     }
 
@@ -25,6 +133,12 @@ impl Config {
                 path
             })
     }
+
+    pub fn bind_address(&self) -> String {
+        self.bind_address
+            .clone()
+            .unwrap_or_else(|| "127.0.0.1:8080".to_string())
+    }
 }
 
 
@@ -44,12 +158,81 @@ impl ConfigBuilder {
         self
     }
 
-    pub fn vault_path(mut self, path: std::path::PathBuf) -> Self {
-        self.config.vault_path = Some(path);
+    pub fn bind_address(mut self, address: String) -> Self {
+        self.config.bind_address = Some(address);
+        self
+    }
+
+    pub fn volume(mut self, volume: VolumeConfig) -> Self {
+        self.config.volumes.push(volume);
         self
     }
 
-    fn build(self) -> Config {
+    /// Shorthand for adding an Obsidian vault as a directory volume.
+    pub fn vault_path(self, path: std::path::PathBuf) -> Self {
+        self.volume(VolumeConfig::new(
+            VolumeKind::Directory,
+            path,
+            vault::volume::flags::Flags::new().with_zettels(),
+        ))
+    }
+
+    /// Load the settings and volumes from a TOML file. Settings made before are overridden.
+    pub fn load_file(mut self, path: &std::path::Path) -> Result<Self, ()> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            eprintln!("Unable to read config file {:?}: {}", path, e);
+        })?;
+
+        let file: ConfigFile = toml::from_str(&content).map_err(|e| {
+            eprintln!("Invalid config file {:?}: {}", path, e);
+        })?;
+
+        let base = path.parent().unwrap_or_else(|| std::path::Path::new("."));
+
+        if let Some(cache_path) = file.cache_path {
+            self.config.cache_path = Some(resolve_path(&cache_path, base));
+        }
+
+        if let Some(bind_address) = file.bind_address {
+            self.config.bind_address = Some(bind_address);
+        }
+
+        for entry in file.volumes {
+            let mut flags = vault::volume::flags::Flags::new();
+
+            // Directories contain zettels unless stated otherwise
+            if entry.zettels.unwrap_or(entry.kind == VolumeKind::Directory) {
+                flags = flags.with_zettels();
+            }
+            if entry.read_only {
+                flags = flags.with_read_only();
+            }
+            if entry.hidden {
+                flags = flags.with_hidden();
+            }
+
+            self = self.volume(VolumeConfig::new(entry.kind, resolve_path(&entry.path, base), flags));
+        }
+
+        Ok(self)
+    }
+
+    /// Load `$ATHENA_CONFIG` or `~/.athena/config.toml`, if it exists.
+    pub fn load_default_file(self) -> Result<Self, ()> {
+        match default_config_path() {
+            Some(path) if path.exists() => self.load_file(&path),
+            _ => Ok(self),
+        }
+    }
+
+    pub(crate) fn build(mut self) -> Config {
+        // Without any configured volumes, fall back to the vault we can find on our own
+        if self.config.volumes.is_empty() {
+            if let Some(path) = suggest_vault_path() {
+                self = self.vault_path(path);
+            }
+        }
+
         self.config
     }
 
@@ -0,0 +1,256 @@
+use crate::{
+    core::{
+        entity,
+        vault::{caching, resource},
+    },
+    formats,
+};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Address {
+    pub name: Option<String>,
+    pub address: Option<String>,
+}
+
+impl Address {
+    fn from_parsed(address: &mail_parser::Addr) -> Self {
+        Self {
+            name: address.name().map(String::from),
+            address: address.address().map(String::from),
+        }
+    }
+
+    fn list_from_parsed(address: Option<&mail_parser::Address>) -> Vec<Self> {
+        address
+            .map(|address| address.iter().map(Self::from_parsed).collect())
+            .unwrap_or_default()
+    }
+
+    /// Render the address the way it would appear in a header, e.g. `Jane Doe <jane@example.com>`.
+    pub fn display(&self) -> String {
+        match (&self.name, &self.address) {
+            (Some(name), Some(address)) => format!("{} <{}>", name, address),
+            (Some(name), None) => name.clone(),
+            (None, Some(address)) => address.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Headers {
+    pub from: Vec<Address>,
+    pub to: Vec<Address>,
+    pub cc: Vec<Address>,
+    pub subject: Option<String>,
+    pub date: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub message_id: Option<String>,
+    pub in_reply_to: Vec<String>,
+    pub references: Vec<String>,
+}
+
+impl Headers {
+    fn from_message(message: &formats::email::Message) -> Self {
+        let date = message
+            .date()
+            .and_then(|date| chrono::DateTime::parse_from_rfc3339(&date.to_rfc3339()).ok());
+
+        Self {
+            from: Address::list_from_parsed(message.from()),
+            to: Address::list_from_parsed(message.to()),
+            cc: Address::list_from_parsed(message.cc()),
+            subject: message.subject().map(String::from),
+            date,
+            message_id: message.message_id().map(String::from),
+            in_reply_to: Self::message_id_list(message.in_reply_to()),
+            references: Self::message_id_list(message.references()),
+        }
+    }
+
+    fn message_id_list(value: &mail_parser::HeaderValue) -> Vec<String> {
+        value
+            .as_text_list()
+            .map(|ids| ids.iter().map(|id| id.to_string()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A MIME part of an email that is not part of its body.
+pub struct Attachment {
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub content_id: Option<String>,
+    pub content: Vec<u8>,
+}
+
+impl Attachment {
+    fn from_part(part: &mail_parser::MessagePart) -> Self {
+        use mail_parser::MimeHeaders;
+
+        let mime_type = part.content_type().map(|content_type| match content_type.subtype() {
+            Some(subtype) => format!("{}/{}", content_type.ctype(), subtype),
+            None => content_type.ctype().to_string(),
+        });
+
+        Self {
+            name: part.attachment_name().map(String::from),
+            mime_type: mime_type.map(|mime_type| mime_type.to_lowercase()),
+            content_id: part.content_id().map(String::from),
+            content: part.contents().to_vec(),
+        }
+    }
+
+    /// Guess the type from the file name, falling back to the MIME type given in the email.
+    pub fn resource_type(&self) -> resource::Type {
+        let by_extension = self
+            .name
+            .as_deref()
+            .and_then(|name| std::path::Path::new(name).extension())
+            .and_then(|extension| resource::Type::from_extension(&extension.to_string_lossy().to_lowercase()));
+
+        by_extension
+            .or_else(|| {
+                let mime_type = self.mime_type.as_deref()?;
+                resource::Type::all()
+                    .into_iter()
+                    .find(|t| t.mime_type() == mime_type)
+            })
+            .unwrap_or(resource::Type::Unknown)
+    }
+
+    pub fn to_file_content(&self, index: usize) -> resource::file::FileContent {
+        let title = self
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Attachment {}", index + 1));
+
+        resource::file::FileContent::new(self.resource_type(), Some(title), self.content.clone())
+    }
+}
+
+pub struct Email {
+    pub headers: Headers,
+    text: Option<String>,
+    sanitized_html: Option<String>,
+    attachments: Vec<Attachment>,
+}
+
+impl Email {
+    pub(crate) fn from_message(message: formats::email::Message) -> Result<Email, ()> {
+        let headers = Headers::from_message(&message);
+
+        // mail-parser converts between text and HTML if only one of them exists, we only want the original
+        let text = message
+            .text_bodies()
+            .filter_map(|part| match &part.body {
+                mail_parser::PartType::Text(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .reduce(|a, b| a + "\n\n" + &b);
+
+        let html = message
+            .html_bodies()
+            .filter_map(|part| match &part.body {
+                mail_parser::PartType::Html(html) => Some(html.to_string()),
+                _ => None,
+            })
+            .reduce(|a, b| a + &b);
+
+        let attachments = message.attachments().map(Attachment::from_part).collect();
+
+        // Inline images refer to attachments through `cid:` URLs, which are resolved when rendering
+        let sanitized_html = html.map(|html| {
+            ammonia::Builder::default()
+                .add_url_schemes(&["cid"])
+                .clean(&html)
+                .to_string()
+        });
+
+        Ok(Email {
+            headers,
+            text,
+            sanitized_html,
+            attachments,
+        })
+    }
+
+    pub(crate) fn from_rfc822(content: &[u8]) -> Result<Email, ()> {
+        Email::from_message(formats::email::parse_rfc822(content)?)
+    }
+
+    pub fn thread_entry(&self) -> caching::threads::ThreadEntry {
+        caching::threads::ThreadEntry {
+            message_id: self.headers.message_id.clone(),
+            in_reply_to: self.headers.in_reply_to.clone(),
+            references: self.headers.references.clone(),
+            date: self.headers.date,
+        }
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.headers.subject.as_deref()
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.subject()
+            .map(str::trim)
+            .filter(|subject| !subject.is_empty())
+            .map(String::from)
+    }
+
+    /// The plain text body. Falls back to the text contained in the HTML body.
+    pub fn text(&self) -> Option<String> {
+        self.text.clone().or_else(|| {
+            self.sanitized_html
+                .as_deref()
+                .map(mail_parser::decoders::html::html_to_text)
+        })
+    }
+
+    /// The HTML body with everything unsafe (scripts, event handlers, ...) removed.
+    pub fn sanitized_html(&self) -> Option<&str> {
+        self.sanitized_html.as_deref()
+    }
+
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    pub fn attachment(&self, index: usize) -> Option<&Attachment> {
+        self.attachments.get(index)
+    }
+
+    /// Point `cid:` URLs to the download URI of the attachment they refer to.
+    fn resolve_content_ids(&self, html: &str, id: &entity::Id) -> String {
+        let mut html = html.to_string();
+
+        for (index, attachment) in self.attachments.iter().enumerate() {
+            if let Some(content_id) = &attachment.content_id {
+                let content_id = content_id.trim_start_matches('<').trim_end_matches('>');
+                html = html.replace(
+                    &format!("\"cid:{}\"", content_id),
+                    &format!("\"{}\"", id.attachment(index).as_safe_download_uri()),
+                );
+            }
+        }
+
+        html
+    }
+
+    /// Render the body, preferring the HTML version if there is one.
+    pub fn body_as_html(&self, id: &entity::Id) -> maud::Markup {
+        if let Some(html) = self.sanitized_html() {
+            maud::html! {
+                div class="email-body" { (maud::PreEscaped(self.resolve_content_ids(html, id))) }
+            }
+        } else if let Some(text) = &self.text {
+            maud::html! {
+                pre class="email-body email-text" { (text) }
+            }
+        } else {
+            maud::html! {
+                p { "This email has no content." }
+            }
+        }
+    }
+}
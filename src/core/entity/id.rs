@@ -7,6 +7,8 @@ pub enum Id {
     Sha256(Sha256),
     Email(Sha256),
     Basic(String),
+    /// A part of another entity, e.g. the n-th attachment of an email.
+    Attachment(Box<Id>, usize),
 }
 
 impl Id {
@@ -21,6 +23,14 @@ impl Id {
     pub(crate) fn from_string<S: ToString>(string: S) -> Result<Id, ()> {
         let string = string.to_string();
 
+        // Attachments are addressed relative to their email, e.g. `email-<sha>/attachment/0`.
+        // Anything else is a path like `notes/attachment/2`.
+        if let Some((parent, index)) = string.rsplit_once("/attachment/") {
+            if let (Ok(index), Ok(parent @ Id::Email(_))) = (index.parse::<usize>(), Id::from_string(parent)) {
+                return Ok(Id::Attachment(Box::new(parent), index));
+            }
+        }
+
         // If the string starts with "sha256-" then it's a SHA256 hash
 
         if string.starts_with("sha256-") {
@@ -74,14 +84,37 @@ impl Id {
         }
     }
 
+    /// Like `for_resource`, but never looks at the content of the resource.
+    /// Returns `None` if the ID depends on a hash we don't know (e.g. because the file is gone).
+    pub(crate) fn for_resource_if_known(
+        resource: &vault::resource::Resource,
+        resource_interface: &dyn resource::ResourceInterface,
+        cache: &mut caching::GlobalCache,
+    ) -> Option<Id> {
+        match resource.resource_type() {
+            Some(resource::Type::Other(resource::types::OtherType::Email)) => {
+                Some(Id::for_resource(resource, resource_interface, cache))
+            }
+            _ if resource.is_usually_hash_addressable() => {
+                cache.get_hash(resource.volume_path()).map(Id::from_sha256)
+            }
+            _ => Some(Id::for_resource(resource, resource_interface, cache)),
+        }
+    }
+
     pub fn id(&self) -> String {
         match self {
             Id::Sha256(sha256) => format!("sha256-{}", sha256.as_string()),
             Id::Email(sha256) => format!("email-{}", sha256.as_string()),
             Id::Basic(string) => string.clone(),
+            Id::Attachment(parent, index) => format!("{}/attachment/{}", parent.id(), index),
         }
     }
 
+    pub fn attachment(&self, index: usize) -> Id {
+        Id::Attachment(Box::new(self.clone()), index)
+    }
+
     pub fn as_readable_string(&self) -> String {
         self.id()
     }
@@ -160,3 +193,23 @@ impl TypedId {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Id;
+
+    #[test]
+    fn only_emails_have_attachments() {
+        let email = format!("email-{}", "ab".repeat(32));
+
+        let id = Id::from_string(format!("{}/attachment/2", email)).unwrap();
+        assert_eq!(id, Id::Attachment(Box::new(Id::from_string(&email).unwrap()), 2));
+        assert_eq!(id.id(), format!("{}/attachment/2", email));
+
+        assert_eq!(Id::from_string("notes/attachment/2").unwrap(), Id::Basic("notes/attachment/2".to_string()));
+        assert_eq!(
+            Id::from_string("projects/attachment/1.md").unwrap(),
+            Id::Basic("projects/attachment/1.md".to_string())
+        );
+    }
+}
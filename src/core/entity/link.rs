@@ -2,9 +2,60 @@
 pub mod reference {
     use crate::core::entity;
 
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(tag = "type", content = "content", rename_all = "snake_case")]
     pub enum Reference {
         Entity(entity::Id),
         Url(url::Url),
     }
+
+    /// The part of an entity a link points to, written after a `#`.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(tag = "type", content = "content", rename_all = "snake_case")]
+    pub enum Anchor {
+        Heading(String),
+        /// A block marked with `^id`, without the `^`.
+        Block(String),
+    }
+
+    impl Anchor {
+        /// The id of the element the anchor points to in the rendered HTML.
+        pub fn html_id(&self) -> String {
+            match self {
+                // `note#Chapter#Section` points to the section inside of the chapter
+                Anchor::Heading(heading) => heading_html_id(heading.rsplit('#').next().unwrap_or(heading)),
+                Anchor::Block(id) => block_html_id(id),
+            }
+        }
+    }
+
+    /// How the anchor is written after the `#`.
+    impl std::fmt::Display for Anchor {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                Anchor::Heading(heading) => write!(f, "{}", heading),
+                Anchor::Block(id) => write!(f, "^{}", id),
+            }
+        }
+    }
+
+    /// The id of a heading in the rendered HTML, e.g. `hello-world` for "Hello, world!".
+    pub fn heading_html_id(text: &str) -> String {
+        let mut id = String::new();
+
+        for c in text.trim().chars() {
+            if c.is_alphanumeric() {
+                id.extend(c.to_lowercase());
+            } else if (c.is_whitespace() || c == '-') && !id.is_empty() && !id.ends_with('-') {
+                id.push('-');
+            }
+        }
+
+        id.trim_end_matches('-').to_string()
+    }
+
+    /// The id of a block marked with `^id` in the rendered HTML.
+    pub fn block_html_id(id: &str) -> String {
+        format!("^{}", id)
+    }
 }
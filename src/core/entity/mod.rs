@@ -2,6 +2,7 @@ use crate::semantic;
 use crate::core::vault;
 
 
+pub mod email;
 pub mod id;
 pub mod link;
 
@@ -12,6 +13,7 @@ pub type Id = id::Id;
 pub enum Entity {
     File(vault::resource::file::FileContent), // TODO: Rework this
     Zettel(zettel::Zettel),
+    Email(email::Email),
 }
 
 impl Entity {
@@ -28,6 +30,12 @@ impl Entity {
                     Err(_) => Err(()),
                 }
             }
+            Some(vault::resource::Type::Other(vault::resource::types::OtherType::Email)) => {
+                match resource.parse(crate::formats::email::parse_email, resource_interface) {
+                    Ok(message) => Ok(Entity::Email(email::Email::from_message(message)?)),
+                    Err(_) => Err(()),
+                }
+            }
             Some(_) => {
                 // TODO: Check file size and decide if it's too big to read into memory
                 resource.read_content(resource_interface).map(|content| Entity::File(content)).map_err(|_| ())
@@ -41,6 +49,7 @@ impl semantic::Scannable for Entity {
     fn iterate_info_items<F: FnMut(semantic::InfoItem)>(&self, func: &mut F) {
         match self {
             Entity::File(_) => {}
+            Entity::Email(_) => {}
             Entity::Zettel(zettel) => zettel.iterate_info_items(func),
         }
     }
@@ -1,7 +1,8 @@
 use crate::semantic;
+use crate::util::span::{Span, Spanned};
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 pub struct Heading {
     pub level: u8,
     pub nodes: super::Nodes,
@@ -11,84 +12,434 @@ impl Heading {
     pub fn new(level: u8, text: super::Nodes) -> Heading {
         Heading { level, nodes: text }
     }
+
+    /// The id of the heading in the rendered HTML, which links with an anchor point to.
+    pub fn html_id(&self) -> String {
+        crate::core::entity::link::reference::heading_html_id(&super::node::nodes_to_plain_text(&self.nodes))
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 pub struct CodeBlock {
     pub language: Option<String>,
     pub code: String,
 }
 
 pub mod callout {
-    use crate::core::entity::zettel::document::Blocks;
-
+    use crate::core::entity::zettel::document::{Blocks, Nodes};
 
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
     pub enum Kind {
+        /// A plain quote without a `[!kind]`.
         Basic,
-        Quote,
         Note,
-        Warning,
+        Abstract,
         Info,
-        Error,
+        Todo,
+        Tip,
+        Success,
+        Question,
+        Warning,
+        Failure,
+        Danger,
+        Bug,
+        Example,
+        Quote,
+        /// Any other kind, only styled through its CSS class.
+        Custom(String),
     }
 
     impl From<&str> for Kind {
         fn from(s: &str) -> Kind {
-            match s {
-                "quote"   => Kind::Quote,
-                "note"    => Kind::Note,
-                "warning" => Kind::Warning,
-                "info"    => Kind::Info,
-                "error"   => Kind::Error,
-                _         => Kind::Basic,
+            match s.to_lowercase().as_str() {
+                "note"                                => Kind::Note,
+                "abstract" | "summary" | "tldr"       => Kind::Abstract,
+                "info"                                => Kind::Info,
+                "todo"                                => Kind::Todo,
+                "tip" | "hint" | "important"          => Kind::Tip,
+                "success" | "check" | "done"          => Kind::Success,
+                "question" | "help" | "faq"           => Kind::Question,
+                "warning" | "caution" | "attention"   => Kind::Warning,
+                "failure" | "fail" | "missing"        => Kind::Failure,
+                "danger" | "error"                    => Kind::Danger,
+                "bug"                                 => Kind::Bug,
+                "example"                             => Kind::Example,
+                "quote" | "cite"                      => Kind::Quote,
+                other                                 => Kind::Custom(other.to_string()),
             }
         }
     }
 
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    impl Kind {
+        /// The name written between `[!` and `]`, `None` for a plain quote.
+        pub fn name(&self) -> Option<&str> {
+            match self {
+                Kind::Basic => None,
+                Kind::Note => Some("note"),
+                Kind::Abstract => Some("abstract"),
+                Kind::Info => Some("info"),
+                Kind::Todo => Some("todo"),
+                Kind::Tip => Some("tip"),
+                Kind::Success => Some("success"),
+                Kind::Question => Some("question"),
+                Kind::Warning => Some("warning"),
+                Kind::Failure => Some("failure"),
+                Kind::Danger => Some("danger"),
+                Kind::Bug => Some("bug"),
+                Kind::Example => Some("example"),
+                Kind::Quote => Some("quote"),
+                Kind::Custom(name) => Some(name),
+            }
+        }
+
+        /// The title shown when the callout has none of its own, e.g. "Note".
+        pub fn default_title(&self) -> String {
+            let name = self.name().unwrap_or_default().replace(['-', '_'], " ");
+            let mut chars = name.chars();
+
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+
+    /// Whether a foldable callout starts out expanded (`+`) or collapsed (`-`).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Fold {
+        Expanded,
+        Collapsed,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
     pub struct Callout {
         pub kind: Kind,
+        /// `None` if the callout can't be folded.
+        pub fold: Option<Fold>,
+        /// The text behind the kind, empty if the default title is used.
+        pub title: Nodes,
         pub blocks: Blocks,
     }
 
     impl Callout {
         pub fn new(kind: Kind, blocks: Blocks) -> Callout {
-            Callout { kind, blocks }
+            Callout {
+                kind,
+                fold: None,
+                title: Nodes::new(),
+                blocks,
+            }
+        }
+
+        pub fn with_fold(mut self, fold: Option<Fold>) -> Callout {
+            self.fold = fold;
+            self
+        }
+
+        pub fn with_title(mut self, title: Nodes) -> Callout {
+            self.title = title;
+            self
         }
     }
 }
 
 pub mod bullet_point {
     use super::super::Nodes;
+    use crate::util::span::Span;
 
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Status {
+        Pending,
+        Done,
+        Cancelled,
+        InProgress,
+    }
+
+    impl Status {
+        /// The character between the brackets of the checkbox.
+        pub fn as_char(&self) -> char {
+            match self {
+                Status::Pending => ' ',
+                Status::Done => 'x',
+                Status::Cancelled => '-',
+                Status::InProgress => '/',
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Priority {
+        Lowest,
+        Low,
+        Medium,
+        High,
+        Highest,
+    }
+
+    impl Priority {
+        fn from_emoji(emoji: &str) -> Option<Priority> {
+            match emoji {
+                "⏬" => Some(Priority::Lowest),
+                "🔽" => Some(Priority::Low),
+                "🔼" => Some(Priority::Medium),
+                "⏫" => Some(Priority::High),
+                "🔺" => Some(Priority::Highest),
+                _ => None,
+            }
+        }
+
+        fn from_name(name: &str) -> Option<Priority> {
+            match name.to_lowercase().as_str() {
+                "lowest" => Some(Priority::Lowest),
+                "low" => Some(Priority::Low),
+                "medium" => Some(Priority::Medium),
+                "high" => Some(Priority::High),
+                "highest" => Some(Priority::Highest),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
     pub struct TaskInfo {
+        pub status: Status,
+        pub due: Option<chrono::NaiveDate>,
+        pub scheduled: Option<chrono::NaiveDate>,
+        pub priority: Option<Priority>,
+    }
+
+    impl TaskInfo {
+        pub fn new(status: Status) -> TaskInfo {
+            TaskInfo {
+                status,
+                due: None,
+                scheduled: None,
+                priority: None,
+            }
+        }
+
+        /// Pick up the dates and the priority from the text of the task. Both the emojis of
+        /// the Obsidian Tasks plugin (`📅 2026-10-20`, `⏳ ...`, `⏫`) and the `due:2026-10-20`,
+        /// `scheduled:...` and `priority:high` notations are understood.
+        pub fn parse(status: Status, text: &str) -> TaskInfo {
+            let mut info = TaskInfo::new(status);
+            let words = text.split_whitespace().collect::<Vec<_>>();
+
+            let parse_date = |text: &str| chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").ok();
+
+            for (index, word) in words.iter().enumerate() {
+                // The value is either glued to the marker or the next word
+                let value = |marker: &str| {
+                    let rest = word.strip_prefix(marker)?;
+
+                    if rest.is_empty() {
+                        words.get(index + 1).copied()
+                    } else {
+                        Some(rest)
+                    }
+                };
+
+                if let Some(date) = value("📅").or_else(|| value("due:")).and_then(parse_date) {
+                    info.due = Some(date);
+                } else if let Some(date) = value("⏳").or_else(|| value("scheduled:")).and_then(parse_date) {
+                    info.scheduled = Some(date);
+                } else if let Some(priority) = value("priority:").and_then(Priority::from_name) {
+                    info.priority = Some(priority);
+                } else if let Some(priority) = Priority::from_emoji(word) {
+                    info.priority = Some(priority);
+                }
+            }
+
+            info
+        }
+
+        /// Pending and in-progress tasks still need to be done.
+        pub fn is_open(&self) -> bool {
+            matches!(self.status, Status::Pending | Status::InProgress)
+        }
 
+        pub fn is_overdue(&self, today: chrono::NaiveDate) -> bool {
+            self.is_open() && self.due.is_some_and(|due| due < today)
+        }
     }
 
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
     pub struct BulletPoint {
         pub task_info: Option<TaskInfo>,
         pub nodes: Nodes,
+        /// The id from a trailing `^id`, to link to the bullet point.
+        pub block_id: Option<String>,
+        /// Lists nested below this bullet point.
+        pub children: Vec<super::list::List>,
+        /// The line of the source file the bullet point is written in, without the nested lists.
+        pub span: Span,
+    }
+}
+
+pub mod list {
+    use super::bullet_point::BulletPoint;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum Kind {
+        Unordered,
+        Ordered { start: u64 },
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+    pub struct List {
+        pub kind: Kind,
+        /// How deep the list is nested inside other lists, starting at 0.
+        pub depth: usize,
+        pub items: Vec<BulletPoint>,
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub mod table {
+    use super::super::Nodes;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Alignment {
+        Left,
+        Center,
+        Right,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+    pub struct Table {
+        /// One entry per column, `None` if the column has no alignment of its own.
+        pub alignments: Vec<Option<Alignment>>,
+        pub header: Vec<Nodes>,
+        pub rows: Vec<Vec<Nodes>>,
+    }
+
+    impl Table {
+        /// The header row followed by all other rows.
+        pub fn all_rows(&self) -> impl Iterator<Item = &Vec<Nodes>> {
+            std::iter::once(&self.header).chain(self.rows.iter())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 pub struct Paragraph {
     pub nodes: super::Nodes,
+    /// The id from a trailing `^id`, to link to the paragraph.
+    pub block_id: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
 pub enum Block {
     Heading(Heading),
     Line,
     CodeBlock(CodeBlock),
     Callout(callout::Callout),
-    BulletPoint(bullet_point::BulletPoint),
+    List(list::List),
+    Table(table::Table),
     Paragraph(Paragraph),
 }
 
+impl Block {
+    /// Call `func` for every block that does not contain other blocks.
+    pub fn visit_leaves<F: FnMut(&Block)>(&self, func: &mut F) {
+        match self {
+            Block::Callout(callout) => {
+                for block in &callout.blocks {
+                    block.visit_leaves(func);
+                }
+            }
+            Block::List(list) => list.visit_leaves(func),
+            Block::Table(table) => {
+                // Every row is visited on its own, with the cells separated like in the source
+                for row in table.all_rows() {
+                    let mut nodes = Vec::new();
+
+                    for (index, cell) in row.iter().enumerate() {
+                        // The separator is made up, it gets an empty span after the previous cell
+                        if index > 0 {
+                            let end = nodes.last().map(|node: &Spanned<_>| node.span.end).unwrap_or_default();
+                            nodes.push(Spanned::new(
+                                super::node::Node::Text(" | ".to_string()),
+                                Span::new(end, end),
+                            ));
+                        }
+                        nodes.extend(cell.iter().cloned());
+                    }
+
+                    func(&Block::Paragraph(Paragraph { nodes, block_id: None }));
+                }
+            }
+            _ => func(self),
+        }
+    }
+
+    pub fn to_plain_text(&self) -> String {
+        use super::node::nodes_to_plain_text;
+
+        match self {
+            Block::Heading(heading) => nodes_to_plain_text(&heading.nodes),
+            Block::Line => String::new(),
+            Block::CodeBlock(code_block) => code_block.code.clone(),
+            Block::Callout(callout) => callout
+                .blocks
+                .iter()
+                .map(|block| block.to_plain_text())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::List(list) => list.to_plain_text(),
+            Block::Table(table) => table
+                .all_rows()
+                .map(|row| {
+                    row.iter()
+                        .map(nodes_to_plain_text)
+                        .collect::<Vec<_>>()
+                        .join(" | ")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Paragraph(paragraph) => nodes_to_plain_text(&paragraph.nodes),
+        }
+    }
+}
+
+impl list::List {
+    /// Every bullet point is visited as a paragraph of its own, so that it can be found without its siblings.
+    pub fn visit_leaves<F: FnMut(&Block)>(&self, func: &mut F) {
+        for bullet_point in &self.items {
+            func(&Block::Paragraph(Paragraph {
+                nodes: bullet_point.nodes.clone(),
+                block_id: bullet_point.block_id.clone(),
+            }));
+
+            for child in &bullet_point.children {
+                child.visit_leaves(func);
+            }
+        }
+    }
+
+    pub fn to_plain_text(&self) -> String {
+        use super::node::nodes_to_plain_text;
+
+        let mut lines = Vec::new();
+
+        for bullet_point in &self.items {
+            lines.push(nodes_to_plain_text(&bullet_point.nodes));
+
+            for child in &bullet_point.children {
+                lines.push(child.to_plain_text());
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
 impl semantic::Scannable for Block {
     fn iterate_info_items<F: FnMut(semantic::InfoItem)>(&self, func: &mut F) {
         match self {
@@ -96,8 +447,122 @@ impl semantic::Scannable for Block {
             Block::Line => {}
             Block::CodeBlock(_) => {}
             Block::Callout(callout) => callout.blocks.iterate_info_items(func),
-            Block::BulletPoint(bullet_point) => bullet_point.nodes.iterate_info_items(func),
+            Block::List(list) => list.iterate_info_items(func),
+            Block::Table(table) => {
+                for cell in table.all_rows().flatten() {
+                    cell.iterate_info_items(func);
+                }
+            }
             Block::Paragraph(paragraph) => paragraph.nodes.iterate_info_items(func),
         }
     }
 }
+
+impl semantic::Scannable for list::List {
+    fn iterate_info_items<F: FnMut(semantic::InfoItem)>(&self, func: &mut F) {
+        for bullet_point in &self.items {
+            if let Some(task_info) = &bullet_point.task_info {
+                let mut tags = Vec::new();
+
+                bullet_point.nodes.iterate_info_items(&mut |item| {
+                    if let semantic::InfoItem::Tag(tag) = item {
+                        tags.push(tag);
+                    }
+                });
+
+                func(semantic::InfoItem::Task(semantic::Task {
+                    info: task_info.clone(),
+                    text: super::node::nodes_to_plain_text(&bullet_point.nodes).trim().to_string(),
+                    tags,
+                }));
+            }
+
+            bullet_point.nodes.iterate_info_items(func);
+
+            for child in &bullet_point.children {
+                child.iterate_info_items(func);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::markdown::testing::parse_blocks;
+    use crate::semantic::{InfoItem, Scannable};
+
+    #[test]
+    fn table_cells_are_scanned() {
+        let blocks = parse_blocks("| a | b |\n|---|---|\n| [[target]] | #tag |\n");
+
+        let mut links = 0;
+        let mut tags = 0;
+
+        blocks.iterate_info_items(&mut |item| match item {
+            InfoItem::Link(_) => links += 1,
+            InfoItem::Tag(_) => tags += 1,
+            _ => {}
+        });
+
+        assert_eq!((links, tags), (1, 1));
+    }
+
+    #[test]
+    fn tasks_carry_status_dates_and_priority() {
+        use super::bullet_point::{Priority, Status};
+
+        let blocks = parse_blocks("- [x] done\n- [/] report 📅 2026-10-20 ⏳2026-10-18 🔼 #work\n\t- [-] old due:2026-01-01 priority:low\n");
+        let mut tasks = Vec::new();
+
+        blocks.iterate_info_items(&mut |item| {
+            if let InfoItem::Task(task) = item {
+                tasks.push(task);
+            }
+        });
+
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].info.status, Status::Done);
+
+        let date = |text| chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").ok();
+
+        assert_eq!(tasks[1].info.status, Status::InProgress);
+        assert_eq!(tasks[1].info.due, date("2026-10-20"));
+        assert_eq!(tasks[1].info.scheduled, date("2026-10-18"));
+        assert_eq!(tasks[1].info.priority, Some(Priority::Medium));
+        assert_eq!(tasks[1].tags, vec!["work".to_string()]);
+        assert!(tasks[1].info.is_overdue(date("2026-10-21").unwrap()));
+
+        assert_eq!(tasks[2].info.status, Status::Cancelled);
+        assert_eq!(tasks[2].info.due, date("2026-01-01"));
+        assert_eq!(tasks[2].info.priority, Some(Priority::Low));
+        assert!(!tasks[2].info.is_overdue(date("2026-10-21").unwrap()));
+    }
+
+    #[test]
+    fn callout_kinds_and_their_titles() {
+        use super::callout::Kind;
+
+        let aliases = [
+            ("NOTE", Kind::Note),
+            ("tldr", Kind::Abstract),
+            ("hint", Kind::Tip),
+            ("done", Kind::Success),
+            ("faq", Kind::Question),
+            ("caution", Kind::Warning),
+            ("missing", Kind::Failure),
+            ("error", Kind::Danger),
+            ("cite", Kind::Quote),
+        ];
+
+        for (name, kind) in aliases {
+            assert_eq!(Kind::from(name), kind, "{}", name);
+        }
+
+        assert_eq!(Kind::from("Custom_Thing"), Kind::Custom("custom_thing".to_string()));
+
+        assert_eq!(Kind::Question.default_title(), "Question");
+        assert_eq!(Kind::from("my-kind").default_title(), "My kind");
+        assert_eq!(Kind::Basic.name(), None);
+        assert_eq!(Kind::Basic.default_title(), "");
+    }
+}
@@ -3,21 +3,135 @@ use crate::core::{
     vault,
 };
 
+/// The page listing everything with the tag, nested tags keep their slashes.
+pub fn tag_uri(tag: &str) -> String {
+    let segments = tag.split('/').map(crate::util::percent_encode).collect::<Vec<_>>();
+    format!("/tags/{}", segments.join("/"))
+}
+
+/// How deep embeds may be nested inside of each other, deeper ones are only linked.
+const MAX_EMBED_DEPTH: usize = 5;
+
 pub struct HtmlConversionContext {
     vault: std::sync::Arc<vault::Vault>,
+    editable_tasks: bool,
+    /// The notes (or sections of them) currently being rendered, the outermost first.
+    embed_stack: Vec<(entity::Id, Option<link::reference::Anchor>)>,
 }
 
 impl HtmlConversionContext {
     pub fn new(vault: std::sync::Arc<vault::Vault>) -> Self {
-        Self { vault }
+        Self {
+            vault,
+            editable_tasks: false,
+            embed_stack: Vec::new(),
+        }
     }
 
-    fn generate_embed(&self, id: &entity::Id) -> maud::PreEscaped<String> {
-        if let Some(entity) = self.vault.load_entity(id) {
-            crate::util::embedding::embed_entity_for_id(&entity, id, self)
-        } else {
-            maud::html! {
-                p { "Failed to load entity" }
+    /// Render clickable checkboxes that know their line, so that tasks can be toggled.
+    pub fn with_editable_tasks(mut self) -> Self {
+        self.editable_tasks = true;
+        self
+    }
+
+    /// The entity whose content is converted, so that it doesn't get embedded into itself.
+    pub fn for_entity(mut self, id: &entity::Id) -> Self {
+        self.embed_stack = vec![(self.canonical_id(id), None)];
+        self
+    }
+
+    fn canonical_id(&self, id: &entity::Id) -> entity::Id {
+        self.vault.canonical_id(id).unwrap_or_else(|| id.clone())
+    }
+
+    /// Whether embedding the target would embed something into itself. A section may be
+    /// embedded into another part of its own note, but not into itself.
+    fn is_circular(&self, id: &entity::Id, anchor: Option<&link::reference::Anchor>) -> bool {
+        self.embed_stack
+            .iter()
+            .any(|(embedding, embedding_anchor)| embedding == id && (anchor.is_none() || embedding_anchor.as_ref() == anchor))
+    }
+
+    fn generate_embed(&self, id: &entity::Id, anchor: Option<&link::reference::Anchor>) -> maud::PreEscaped<String> {
+        use maud::html;
+
+        let entity = match self.vault.load_entity(id) {
+            Some(entity) => entity,
+            None => {
+                return html! {
+                    p { "Failed to load entity" }
+                }
+            }
+        };
+
+        let zettel = match &entity {
+            entity::Entity::Zettel(zettel) => zettel,
+            // The lines of embedded tasks belong to another file, so they can't be edited from here
+            _ => {
+                return crate::util::embedding::embed_entity_for_id(
+                    &entity,
+                    id,
+                    &HtmlConversionContext::new(std::sync::Arc::clone(&self.vault)),
+                )
+            }
+        };
+
+        let title = self.vault.title_of_entity(id).unwrap_or_else(|| id.as_readable_string());
+        let (uri, title) = match anchor {
+            Some(anchor) => (
+                format!("{}#{}", id.as_safe_uri(), anchor.html_id()),
+                format!("{} › {}", title, anchor),
+            ),
+            None => (id.as_safe_uri(), title),
+        };
+
+        let canonical_id = self.canonical_id(id);
+
+        if self.is_circular(&canonical_id, anchor) {
+            return html! {
+                div class="embed embed-placeholder" {
+                    "Circular embed: " a href=(uri) { (title) }
+                }
+            };
+        }
+
+        if self.embed_stack.len() > MAX_EMBED_DEPTH {
+            return html! {
+                div class="embed embed-placeholder" {
+                    "Embeds are nested too deeply: " a href=(uri) { (title) }
+                }
+            };
+        }
+
+        let mut embed_stack = self.embed_stack.clone();
+        embed_stack.push((canonical_id, anchor.cloned()));
+
+        let context = HtmlConversionContext {
+            vault: std::sync::Arc::clone(&self.vault),
+            editable_tasks: false,
+            embed_stack,
+        };
+
+        // Only the section the anchor points to is shown
+        let content = match (zettel.body_as_document(), anchor) {
+            (Some(document), Some(anchor)) => match document.section(anchor) {
+                Some(section) => maud::PreEscaped(section.as_html(&context)),
+                None => html! {
+                    p { "Section not found: " (anchor) }
+                },
+            },
+            (Some(document), None) => maud::PreEscaped(document.as_html(&context)),
+            (None, _) => html! {
+                p { "Content not displayed" }
+            },
+        };
+
+        html! {
+            div class="embed" {
+                div class="embed-header" {
+                    a href=(uri) { (title) }
+                }
+                (content)
             }
         }
     }
@@ -33,6 +147,7 @@ fn convert_style(style: &document::node::Style) -> &'static str {
         document::node::Style::Italic => "i",
         document::node::Style::Underline => "u",
         document::node::Style::Strikethrough => "s",
+        document::node::Style::Highlight => "mark",
     }
 }
 
@@ -48,10 +163,12 @@ impl AsHtml for document::node::Node {
                 html! { (text) }.into_string()
             }
             Node::Tag(tag) => {
-                let link = format!("/tags/{}", tag);
-                html! { a href=(link) { code { (format!("#{}", tag)) } } }.into_string()
+                html! { a href=(tag_uri(tag)) { code { (format!("#{}", tag)) } } }.into_string()
             }
             Node::Code(code) => html! { code { (code) } }.into_string(),
+            Node::Math(tex) => html! { span class="math" { (tex) } }.into_string(),
+            Node::Comment(_) => String::new(),
+            Node::Escaped(c) => html! { (c) }.into_string(),
             Node::Styled(style, node) => {
                 let tag_name = convert_style(style);
                 let html = node.as_html(context);
@@ -65,17 +182,48 @@ impl AsHtml for document::node::Node {
                 match target {
                     link::reference::Reference::Entity(target_id) => {
                         if link.embed {
-                            context.generate_embed(target_id).into_string()
+                            context.generate_embed(target_id, link.anchor.as_ref()).into_string()
                         } else {
-                            // TODO: Escape the text?
-                            format!(
-                                "<a href=\"{}\">{}</a>",
-                                target_id.as_safe_uri(),
-                                caption
-                                    .iter()
-                                    .map(|node| node.as_html(context))
-                                    .collect::<String>()
-                            )
+                            let uri = match &link.anchor {
+                                Some(anchor) => format!("{}#{}", target_id.as_safe_uri(), anchor.html_id()),
+                                None => target_id.as_safe_uri(),
+                            };
+
+                            let caption = caption
+                                .iter()
+                                .map(|node| node.as_html(context))
+                                .collect::<String>();
+
+                            // Links by name may point to nothing or to several notes at once
+                            let candidates = match target_id {
+                                entity::Id::Basic(name) => Some(context.vault.link_candidates(name)),
+                                _ => None,
+                            };
+
+                            match candidates {
+                                Some(candidates) if candidates.is_empty() => html! {
+                                    a href=(uri) class="link-missing" title="This note does not exist yet" {
+                                        (maud::PreEscaped(caption))
+                                    }
+                                },
+                                Some(candidates) if candidates.len() > 1 => {
+                                    let paths = candidates
+                                        .iter()
+                                        .map(|resource| resource.volume_path().path().display().to_string())
+                                        .collect::<Vec<_>>()
+                                        .join(", ");
+
+                                    html! {
+                                        a href=(uri) class="link-ambiguous" title=(format!("Ambiguous link, using the first of: {}", paths)) {
+                                            (maud::PreEscaped(caption))
+                                        }
+                                    }
+                                }
+                                _ => html! {
+                                    a href=(uri) { (maud::PreEscaped(caption)) }
+                                },
+                            }
+                            .into_string()
                         }
                     }
                     link::reference::Reference::Url(url) => {
@@ -124,7 +272,13 @@ impl AsHtml for document::block::Heading {
             .map(|node| node.as_html(context))
             .collect::<String>();
 
-        format!("<{}>{}</{}>", tag_name, text, tag_name)
+        let id = self.html_id();
+
+        if id.is_empty() {
+            format!("<{}>{}</{}>", tag_name, text, tag_name)
+        } else {
+            format!("<{} id=\"{}\">{}</{}>", tag_name, maud::html! { (id) }.into_string(), text, tag_name)
+        }
     }
 }
 
@@ -141,34 +295,64 @@ impl AsHtml for document::block::CodeBlock {
 
 impl AsHtml for document::block::callout::Callout {
     fn as_html(&self, context: &HtmlConversionContext) -> String {
+        use document::block::callout::{Fold, Kind};
         use maud::html;
 
-        let class_name = match self.kind {
-            document::block::callout::Kind::Basic => "callout callout-bordered-left callout-basic",
-            document::block::callout::Kind::Quote => "callout callout-bordered callout-quote",
-            document::block::callout::Kind::Note => "callout callout-bordered callout-note",
-            document::block::callout::Kind::Info => "callout callout-bordered callout-info",
-            document::block::callout::Kind::Warning => "callout callout-bordered callout-warning",
-            document::block::callout::Kind::Error => "callout callout-bordered callout-error",
-        };
-
         let blocks = self
             .blocks
             .iter()
             .map(|block| block.as_html(context))
             .collect::<String>();
 
-        html! {
-            div class=(class_name) {
-                (maud::PreEscaped(blocks))
+        let class_name = match &self.kind {
+            Kind::Basic => {
+                return html! {
+                    div class="callout callout-bordered-left callout-basic" {
+                        (maud::PreEscaped(blocks))
+                    }
+                }
+                .into_string();
             }
+            // Custom kinds get a generic look, which can be overridden through their own class
+            Kind::Custom(name) => format!("callout callout-bordered callout-custom callout-{}", css_class_name(name)),
+            kind => format!("callout callout-bordered callout-{}", kind.name().unwrap_or_default()),
+        };
+
+        let title = if self.title.is_empty() {
+            html! { (self.kind.default_title()) }.into_string()
+        } else {
+            self.title.iter().map(|node| node.as_html(context)).collect::<String>()
+        };
+
+        match self.fold {
+            Some(fold) => html! {
+                details class=(class_name) open[fold == Fold::Expanded] {
+                    summary class="callout-title" { (maud::PreEscaped(title)) }
+                    div class="callout-content" { (maud::PreEscaped(blocks)) }
+                }
+            },
+            None => html! {
+                div class=(class_name) {
+                    div class="callout-title" { (maud::PreEscaped(title)) }
+                    div class="callout-content" { (maud::PreEscaped(blocks)) }
+                }
+            },
         }
         .into_string()
     }
 }
 
+/// Only keep the characters that can safely be part of a CSS class.
+fn css_class_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+
 impl AsHtml for document::block::bullet_point::BulletPoint {
     fn as_html(&self, context: &HtmlConversionContext) -> String {
+        use document::block::bullet_point::Status;
         use maud::html;
 
         let html = self
@@ -177,14 +361,100 @@ impl AsHtml for document::block::bullet_point::BulletPoint {
             .map(|node| node.as_html(context))
             .collect::<String>();
 
-        // If the bullet point's task info is Some, we need to render an unclickable checkbox
-        let checkbox = if self.task_info.is_some() {
-            "<input type=\"checkbox\" disabled checked />"
-        } else {
-            ""
+        // If the bullet point's task info is Some, we need to render a checkbox
+        let (class_name, checked) = match self.task_info.as_ref().map(|task_info| task_info.status) {
+            Some(Status::Pending) => ("task task-pending", false),
+            Some(Status::InProgress) => ("task task-in-progress", false),
+            Some(Status::Done) => ("task task-done", true),
+            Some(Status::Cancelled) => ("task task-cancelled", true),
+            None => ("", false),
         };
 
-        html! { li { (maud::PreEscaped(checkbox)) (maud::PreEscaped(html)) } }.into_string()
+        let checkbox = match (&self.task_info, context.editable_tasks, checked) {
+            (None, _, _) => html! {},
+            (Some(_), true, true) => html! { input type="checkbox" class="task-checkbox" data-line=(self.span.start.line) checked; },
+            (Some(_), true, false) => html! { input type="checkbox" class="task-checkbox" data-line=(self.span.start.line); },
+            (Some(_), false, true) => html! { input type="checkbox" disabled checked; },
+            (Some(_), false, false) => html! { input type="checkbox" disabled; },
+        };
+
+        let children = self
+            .children
+            .iter()
+            .map(|list| list.as_html(context))
+            .collect::<String>();
+
+        match &self.block_id {
+            Some(id) => html! { li class=(class_name) id=(link::reference::block_html_id(id)) { (checkbox) (maud::PreEscaped(html)) (maud::PreEscaped(children)) } }.into_string(),
+            None => html! { li class=(class_name) { (checkbox) (maud::PreEscaped(html)) (maud::PreEscaped(children)) } }.into_string(),
+        }
+    }
+}
+
+impl AsHtml for document::block::list::List {
+    fn as_html(&self, context: &HtmlConversionContext) -> String {
+        use document::block::list::Kind;
+        use maud::html;
+
+        let items = self
+            .items
+            .iter()
+            .map(|item| item.as_html(context))
+            .collect::<String>();
+
+        match self.kind {
+            Kind::Unordered => html! { ul { (maud::PreEscaped(items)) } }.into_string(),
+            // `start` is left out for the common case, like in other Markdown renderers
+            Kind::Ordered { start: 1 } => html! { ol { (maud::PreEscaped(items)) } }.into_string(),
+            Kind::Ordered { start } => html! { ol start=(start) { (maud::PreEscaped(items)) } }.into_string(),
+        }
+    }
+}
+
+impl AsHtml for document::block::table::Table {
+    fn as_html(&self, context: &HtmlConversionContext) -> String {
+        use document::block::table::Alignment;
+        use maud::html;
+
+        let row_as_html = |row: &Vec<document::Nodes>, header: bool| {
+            let cells = row
+                .iter()
+                .zip(&self.alignments)
+                .map(|(cell, alignment)| {
+                    let html = maud::PreEscaped(cell.iter().map(|node| node.as_html(context)).collect::<String>());
+
+                    let class_name = match alignment {
+                        Some(Alignment::Left) => "align-left",
+                        Some(Alignment::Center) => "align-center",
+                        Some(Alignment::Right) => "align-right",
+                        None => "",
+                    };
+
+                    if header {
+                        html! { th class=(class_name) { (html) } }.into_string()
+                    } else {
+                        html! { td class=(class_name) { (html) } }.into_string()
+                    }
+                })
+                .collect::<String>();
+
+            html! { tr { (maud::PreEscaped(cells)) } }.into_string()
+        };
+
+        let header = row_as_html(&self.header, true);
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| row_as_html(row, false))
+            .collect::<String>();
+
+        html! {
+            table class="table" {
+                thead { (maud::PreEscaped(header)) }
+                tbody { (maud::PreEscaped(rows)) }
+            }
+        }
+        .into_string()
     }
 }
 
@@ -198,7 +468,12 @@ impl AsHtml for document::block::Paragraph {
             .map(|node| node.as_html(context))
             .collect::<String>();
 
-        html! { p { (maud::PreEscaped(html)) } }.into_string()
+        match &self.block_id {
+            // Nothing but comments
+            None if html.is_empty() => String::new(),
+            Some(id) => html! { p id=(link::reference::block_html_id(id)) { (maud::PreEscaped(html)) } }.into_string(),
+            None => html! { p { (maud::PreEscaped(html)) } }.into_string(),
+        }
     }
 }
 
@@ -211,7 +486,8 @@ impl AsHtml for document::block::Block {
             Block::Line => "<hr>".to_string(),
             Block::CodeBlock(codeblock) => codeblock.as_html(context),
             Block::Callout(callout) => callout.as_html(context),
-            Block::BulletPoint(bullet) => bullet.as_html(context),
+            Block::List(list) => list.as_html(context),
+            Block::Table(table) => table.as_html(context),
             Block::Paragraph(paragraph) => paragraph.as_html(context),
         }
     }
@@ -225,3 +501,73 @@ impl AsHtml for document::Document {
             .collect::<String>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{AsHtml, HtmlConversionContext, MAX_EMBED_DEPTH};
+    use crate::core::{config, entity::Id, vault::Vault};
+    use crate::util::testing::TempDir;
+
+    fn open_vault(dir: &TempDir, notes: &[(&str, &str)]) -> Arc<Vault> {
+        for (name, content) in notes {
+            dir.write(&format!("vault/{}.md", name), content);
+        }
+
+        let vault = config::ConfigBuilder::new()
+            .cache_path(dir.path().join("cache"))
+            .vault_path(dir.path().join("vault"))
+            .open_vault()
+            .unwrap();
+
+        Arc::new(vault)
+    }
+
+    fn render(vault: &Arc<Vault>, name: &str) -> String {
+        let id = Id::Basic(name.to_string());
+        let context = HtmlConversionContext::new(Arc::clone(vault)).for_entity(&id);
+
+        vault.load_zettel(&id).unwrap().body_as_document().unwrap().as_html(&context)
+    }
+
+    #[test]
+    fn notes_are_not_embedded_into_themselves() {
+        let dir = TempDir::new("self-embeds");
+        let vault = open_vault(&dir, &[("a", "A\n\n![[a]]\n"), ("b", "B\n\n![[c]]\n"), ("c", "C\n\n![[b]]\n")]);
+
+        let html = render(&vault, "a");
+        assert_eq!(html.matches("Circular embed: ").count(), 1, "{}", html);
+
+        // b shows c, which would show b again
+        let html = render(&vault, "b");
+        assert_eq!(html.matches("class=\"embed\"").count(), 1, "{}", html);
+        assert_eq!(html.matches("Circular embed: ").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn sections_may_not_embed_their_own_note() {
+        let dir = TempDir::new("section-embeds");
+        let vault = open_vault(&dir, &[("s", "# Head\n\n![[s]]\n\n# Other\n"), ("t", "![[s#Head]]\n")]);
+
+        let html = render(&vault, "t");
+        assert_eq!(html.matches("class=\"embed\"").count(), 1, "{}", html);
+        assert!(html.contains("href=\"/entity/s#head\""), "{}", html);
+        assert_eq!(html.matches("Circular embed: ").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn embeds_are_nested_only_so_deep() {
+        let dir = TempDir::new("deep-embeds");
+        let notes = (0..10)
+            .map(|i| (format!("n{}", i), format!("Note {}\n\n![[n{}]]\n", i, i + 1)))
+            .collect::<Vec<_>>();
+        let notes = notes.iter().map(|(name, content)| (name.as_str(), content.as_str())).collect::<Vec<_>>();
+        let vault = open_vault(&dir, &notes);
+
+        let html = render(&vault, "n0");
+        assert_eq!(html.matches("class=\"embed\"").count(), MAX_EMBED_DEPTH, "{}", html);
+        assert_eq!(html.matches("Embeds are nested too deeply: ").count(), 1, "{}", html);
+        assert!(!html.contains("Circular embed: "), "{}", html);
+    }
+}
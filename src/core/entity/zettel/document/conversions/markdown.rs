@@ -1,19 +1,55 @@
 use crate::core::entity::{self, link};
 use crate::core::entity::zettel::document;
 use crate::formats::markdown;
+use crate::util::span::{Span, Spanned};
 
 pub type ConversionError = ();
 
-fn convert_node(node: &markdown::Node) -> Result<document::node::Node, ConversionError> {
-    fn styled1(
-        node: &markdown::Node,
-        style: document::node::Style,
-    ) -> Result<document::node::Node, ConversionError> {
+fn convert_anchor(anchor: &markdown::Anchor) -> link::reference::Anchor {
+    match anchor {
+        markdown::Anchor::Heading(heading) => link::reference::Anchor::Heading(heading.clone()),
+        markdown::Anchor::Block(id) => link::reference::Anchor::Block(id.clone()),
+    }
+}
+
+/// Obsidian marks blocks with a trailing `^id`, which is cut off the text.
+fn take_block_id(nodes: &mut document::Nodes) -> Option<String> {
+    let last = nodes.last_mut()?;
+
+    let document::node::Node::Text(text) = &last.value else {
+        return None;
+    };
+
+    let (rest, id) = text.trim_end().rsplit_once('^')?;
+
+    if id.is_empty()
+        || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        || !(rest.is_empty() || rest.ends_with(char::is_whitespace))
+    {
+        return None;
+    }
+
+    let id = id.to_string();
+    let rest = rest.trim_end().to_string();
+
+    if rest.is_empty() {
+        nodes.pop();
+    } else {
+        last.span.end = last.span.start.advanced_by(&rest);
+        last.value = document::node::Node::Text(rest);
+    }
+
+    Some(id)
+}
+
+/// `span` is where the node is written, for the parts made up during the conversion.
+fn convert_node(node: &markdown::Node, span: Span) -> Result<document::node::Node, ConversionError> {
+    let styled1 = |node: &markdown::Node, style: document::node::Style| -> Result<document::node::Node, ConversionError> {
         Ok(document::node::Node::Styled(
             style,
-            Box::new(convert_node(node)?),
+            Box::new(convert_node(node, span)?),
         ))
-    }
+    };
 
     match node {
         markdown::Node::Newline => Ok(document::node::Node::Newline),
@@ -21,33 +57,45 @@ fn convert_node(node: &markdown::Node) -> Result<document::node::Node, Conversio
         markdown::Node::Code(code) => Ok(document::node::Node::Code(code.clone())),
         markdown::Node::Bold(node) => styled1(node, document::node::Style::Bold),
         markdown::Node::Italic(node) => styled1(node, document::node::Style::Italic),
+        markdown::Node::Strikethrough(node) => styled1(node, document::node::Style::Strikethrough),
+        markdown::Node::Highlight(node) => styled1(node, document::node::Style::Highlight),
+        markdown::Node::Math(tex) => Ok(document::node::Node::Math(tex.clone())),
+        markdown::Node::Comment(text) => Ok(document::node::Node::Comment(text.clone())),
+        markdown::Node::Escaped(c) => Ok(document::node::Node::Escaped(*c)),
         markdown::Node::Tag(tag) => Ok(document::node::Node::Tag(tag.clone())),
         markdown::Node::Link { embed, link } => {
             let target = &link.target;
 
-            let result = match target {
-                markdown::LinkTarget::Zettel(zettel) => Some(document::node::Node::Reference(document::node::reference::Reference {
-                    target: link::reference::Reference::Entity(entity::Id::from_string(zettel)?),
-                    caption: match &link.title {
-                        Some(title) => convert_nodes(&title)?,
-                        None => vec![document::node::Node::Text(zettel.clone())],
-                    },
-                    embed: *embed,
-                })),
-                markdown::LinkTarget::Url(url) => Some(document::node::Node::Reference(document::node::reference::Reference {
+            match target {
+                // Markdown links to paths inside of the vault are resolved like wiki links
+                markdown::LinkTarget::Zettel(zettel) | markdown::LinkTarget::FreeForm(zettel) => {
+                    let anchor = link.anchor.as_ref().map(convert_anchor);
+
+                    // Without a title, the link is shown the way it is written
+                    let written = match &anchor {
+                        Some(anchor) => format!("{}#{}", zettel, anchor),
+                        None => zettel.clone(),
+                    };
+
+                    Ok(document::node::Node::Reference(document::node::reference::Reference {
+                        target: link::reference::Reference::Entity(entity::Id::from_string(zettel)?),
+                        anchor,
+                        caption: match &link.title {
+                            Some(title) => convert_nodes(&title)?,
+                            None => vec![Spanned::new(document::node::Node::Text(written), span)],
+                        },
+                        embed: *embed,
+                    }))
+                }
+                markdown::LinkTarget::Url(url) => Ok(document::node::Node::Reference(document::node::reference::Reference {
                     target: link::reference::Reference::Url(url.clone()),
+                    anchor: None,
                     caption: match &link.title {
                         Some(title) => convert_nodes(&title)?,
-                        None => vec![document::node::Node::Text(url.to_string())],
+                        None => vec![Spanned::new(document::node::Node::Text(url.to_string()), span)],
                     },
                     embed: *embed,
                 })),
-                markdown::LinkTarget::FreeForm(_) => None,
-            };
-
-            match result {
-                Some(node) => Ok(node),
-                None => Ok(document::node::Node::Text("TODO".to_string())),
             }
         }
         markdown::Node::Nodes(nodes) => {
@@ -57,7 +105,10 @@ fn convert_node(node: &markdown::Node) -> Result<document::node::Node, Conversio
 }
 
 fn convert_nodes(nodes: &markdown::Nodes) -> Result<document::Nodes, ConversionError> {
-    nodes.iter().map(convert_node).collect()
+    nodes
+        .iter()
+        .map(|node| Ok(Spanned::new(convert_node(node, node.span)?, node.span)))
+        .collect()
 }
 
 fn convert_heading(
@@ -79,6 +130,87 @@ fn convert_code(
     })
 }
 
+fn convert_list(list: &markdown::List) -> Result<document::block::list::List, ConversionError> {
+    let items = list
+        .items
+        .iter()
+        .map(|item| {
+            let mut nodes = convert_nodes(&item.nodes)?;
+            let block_id = take_block_id(&mut nodes);
+
+            Ok(document::block::bullet_point::BulletPoint {
+                task_info: item.task.as_ref().map(|status| {
+                    use document::block::bullet_point::{Status, TaskInfo};
+
+                    let status = match status {
+                        markdown::TaskStatus::Pending => Status::Pending,
+                        markdown::TaskStatus::Completed => Status::Done,
+                        markdown::TaskStatus::Cancelled => Status::Cancelled,
+                        markdown::TaskStatus::InProgress => Status::InProgress,
+                    };
+
+                    TaskInfo::parse(status, &document::node::nodes_to_plain_text(&nodes))
+                }),
+                nodes,
+                block_id,
+                children: item
+                    .children
+                    .iter()
+                    .map(convert_list)
+                    .collect::<Result<_, _>>()?,
+                span: item.span,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(document::block::list::List {
+        kind: match list.kind {
+            markdown::ListKind::Unordered => document::block::list::Kind::Unordered,
+            markdown::ListKind::Ordered(start) => document::block::list::Kind::Ordered { start },
+        },
+        depth: list.depth,
+        items,
+    })
+}
+
+fn convert_table(table: &markdown::Table) -> Result<document::block::table::Table, ConversionError> {
+    let convert_row = |row: &Vec<markdown::Nodes>| row.iter().map(convert_nodes).collect::<Result<Vec<_>, _>>();
+
+    Ok(document::block::table::Table {
+        alignments: table
+            .alignments
+            .iter()
+            .map(|alignment| {
+                alignment.map(|alignment| match alignment {
+                    markdown::Alignment::Left => document::block::table::Alignment::Left,
+                    markdown::Alignment::Center => document::block::table::Alignment::Center,
+                    markdown::Alignment::Right => document::block::table::Alignment::Right,
+                })
+            })
+            .collect(),
+        header: convert_row(&table.header)?,
+        rows: table.rows.iter().map(convert_row).collect::<Result<_, _>>()?,
+    })
+}
+
+fn convert_callout(callout: &markdown::Callout) -> Result<document::block::callout::Callout, ConversionError> {
+    use document::block::callout;
+
+    let kind = match &callout.kind {
+        Some(kind) => callout::Kind::from(kind.as_str()),
+        None => callout::Kind::Basic,
+    };
+
+    let fold = callout.fold.map(|fold| match fold {
+        markdown::CalloutFold::Expanded => callout::Fold::Expanded,
+        markdown::CalloutFold::Collapsed => callout::Fold::Collapsed,
+    });
+
+    Ok(callout::Callout::new(kind, convert_blocks(&callout.blocks)?)
+        .with_fold(fold)
+        .with_title(convert_nodes(&callout.title)?))
+}
+
 fn convert_block(block: &markdown::Block) -> Result<document::block::Block, ConversionError> {
     match block {
         markdown::Block::ThematicBreak => Ok(document::block::Block::Line),
@@ -88,37 +220,25 @@ fn convert_block(block: &markdown::Block) -> Result<document::block::Block, Conv
         markdown::Block::Code(lang, code) => {
             convert_code(lang, code).map(document::block::Block::CodeBlock)
         }
-        markdown::Block::Callout(kind, callout) => Ok(document::block::Block::Callout(
-            document::block::callout::Callout::new(
-                match kind {
-                    Some(kind) => document::block::callout::Kind::from(kind.as_str()),
-                    None => document::block::callout::Kind::Basic,
-                },
-                convert_blocks(callout)?,
-            ),
-        )),
-        markdown::Block::BulletPoint(bullet_point) => {
-            // TODO
-            Ok(document::block::Block::BulletPoint(
-                document::block::bullet_point::BulletPoint {
-                    task_info: match bullet_point.0 { // TODO!
-                        Some(_) => Some(document::block::bullet_point::TaskInfo {}),
-                        None => None,
-                    },
-                    nodes: convert_nodes(&bullet_point.1)?
-                }
-            ))
+        markdown::Block::Callout(callout) => {
+            convert_callout(callout).map(document::block::Block::Callout)
+        }
+        markdown::Block::List(list) => convert_list(list).map(document::block::Block::List),
+        markdown::Block::Table(table) => convert_table(table).map(document::block::Block::Table),
+        markdown::Block::Nodes(nodes) => {
+            let mut nodes = convert_nodes(nodes)?;
+            let block_id = take_block_id(&mut nodes);
+
+            Ok(document::block::Block::Paragraph(document::block::Paragraph { nodes, block_id }))
         }
-        markdown::Block::Nodes(nodes) => Ok(document::block::Block::Paragraph(
-            document::block::Paragraph {
-                nodes: convert_nodes(nodes)?,
-            },
-        ))
     }
 }
 
 fn convert_blocks(blocks: &markdown::Blocks) -> Result<document::Blocks, ConversionError> {
-    blocks.iter().map(convert_block).collect()
+    blocks
+        .iter()
+        .map(|block| Ok(Spanned::new(convert_block(block)?, block.span)))
+        .collect()
 }
 
 pub fn markdown_to_document(
@@ -1,3 +1,4 @@
 
 pub mod html;
 pub mod markdown;
+pub mod to_markdown;
@@ -0,0 +1,482 @@
+use crate::core::entity::{
+    link,
+    zettel::{self, document, parts::header},
+};
+use crate::util::span::Spanned;
+
+/// Serialize back into Obsidian-flavoured Markdown, the inverse of `markdown::markdown_to_document`.
+pub trait ToMarkdown {
+    fn to_markdown(&self) -> String;
+}
+
+fn style_delimiter(style: &document::node::Style) -> (&'static str, &'static str) {
+    match style {
+        document::node::Style::Bold => ("**", "**"),
+        document::node::Style::Italic => ("*", "*"),
+        document::node::Style::Underline => ("<u>", "</u>"),
+        document::node::Style::Strikethrough => ("~~", "~~"),
+        document::node::Style::Highlight => ("==", "=="),
+    }
+}
+
+/// Whether the caption is the one the parser makes up for a link without a title.
+fn is_default_caption(caption: &document::Nodes, target: &str) -> bool {
+    matches!(caption.as_slice(), [node] if matches!(&node.value, document::node::Node::Text(text) if text == target))
+}
+
+impl ToMarkdown for document::node::Node {
+    fn to_markdown(&self) -> String {
+        use document::node::Node;
+
+        match self {
+            Node::Newline => "\\\n".to_string(),
+            Node::Text(text) => text.clone(),
+            Node::Tag(tag) => format!("#{}", tag),
+            Node::Code(code) => format!("`{}`", code),
+            Node::Math(tex) => format!("${}$", tex),
+            Node::Comment(text) => format!("%%{}%%", text),
+            Node::Escaped(c) => format!("\\{}", c),
+            Node::Styled(style, node) => {
+                let (open, close) = style_delimiter(style);
+                format!("{}{}{}", open, node.to_markdown(), close)
+            }
+            Node::Reference(reference) => {
+                let embed = if reference.embed { "!" } else { "" };
+
+                match &reference.target {
+                    link::reference::Reference::Entity(id) => {
+                        let target = match &reference.anchor {
+                            Some(anchor) => format!("{}#{}", id.id(), anchor),
+                            None => id.id(),
+                        };
+
+                        if is_default_caption(&reference.caption, &target) {
+                            format!("{}[[{}]]", embed, target)
+                        } else {
+                            format!("{}[[{}|{}]]", embed, target, reference.caption.to_markdown())
+                        }
+                    }
+                    link::reference::Reference::Url(url) => {
+                        let target = url.to_string();
+
+                        // Bare URLs are recognized by the parser on their own
+                        if !reference.embed && is_default_caption(&reference.caption, &target) {
+                            target
+                        } else {
+                            format!("{}[{}]({})", embed, reference.caption.to_markdown(), target)
+                        }
+                    }
+                }
+            }
+            Node::Grouped(nodes) => nodes.to_markdown(),
+        }
+    }
+}
+
+impl<T: ToMarkdown> ToMarkdown for Spanned<T> {
+    fn to_markdown(&self) -> String {
+        self.value.to_markdown()
+    }
+}
+
+impl ToMarkdown for document::Nodes {
+    fn to_markdown(&self) -> String {
+        self.iter().map(ToMarkdown::to_markdown).collect()
+    }
+}
+
+/// The first line of a callout, e.g. `> [!tip]- A title`.
+fn callout_header(callout: &document::block::callout::Callout) -> Option<String> {
+    use document::block::callout::Fold;
+
+    let kind = callout.kind.name()?;
+
+    let fold = match callout.fold {
+        Some(Fold::Expanded) => "+",
+        Some(Fold::Collapsed) => "-",
+        None => "",
+    };
+
+    let title = callout.title.to_markdown();
+
+    if title.is_empty() {
+        Some(format!("> [!{}]{}", kind, fold))
+    } else {
+        Some(format!("> [!{}]{} {}", kind, fold, title))
+    }
+}
+
+/// Append the `^id` that marks a block, if there is one.
+fn with_block_id(text: String, block_id: &Option<String>) -> String {
+    match block_id {
+        Some(id) => format!("{} ^{}", text, id),
+        None => text,
+    }
+}
+
+/// Put `> ` in front of every line, the way callouts are written.
+fn quote_lines(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl ToMarkdown for document::block::Block {
+    fn to_markdown(&self) -> String {
+        use document::block::Block;
+
+        match self {
+            Block::Heading(heading) => format!("{} {}", "#".repeat(heading.level as usize), heading.nodes.to_markdown()),
+            Block::Line => "---".to_string(),
+            Block::CodeBlock(code_block) => format!(
+                "```{}\n{}\n```",
+                code_block.language.as_deref().unwrap_or(""),
+                code_block.code
+            ),
+            Block::Callout(callout) => {
+                let mut lines = Vec::new();
+
+                if let Some(header) = callout_header(callout) {
+                    lines.push(header);
+                }
+
+                if !callout.blocks.is_empty() {
+                    lines.push(quote_lines(&callout.blocks.to_markdown()));
+                }
+
+                lines.join("\n")
+            }
+            Block::List(list) => list.to_markdown(),
+            Block::Table(table) => table.to_markdown(),
+            Block::Paragraph(paragraph) => with_block_id(paragraph.nodes.to_markdown(), &paragraph.block_id),
+        }
+    }
+}
+
+impl ToMarkdown for document::block::list::List {
+    fn to_markdown(&self) -> String {
+        use document::block::list::Kind;
+
+        // Obsidian indents nested lists with tabs
+        let indent = "\t".repeat(self.depth);
+
+        let mut lines = Vec::new();
+
+        for (index, bullet_point) in self.items.iter().enumerate() {
+            let marker = match self.kind {
+                Kind::Unordered => "-".to_string(),
+                Kind::Ordered { start } => format!("{}.", start + index as u64),
+            };
+
+            let line = match &bullet_point.task_info {
+                // The parser keeps the space after the checkbox as part of the text
+                Some(task_info) => format!(
+                    "{}{} [{}]{}",
+                    indent,
+                    marker,
+                    task_info.status.as_char(),
+                    bullet_point.nodes.to_markdown()
+                ),
+                None => format!("{}{} {}", indent, marker, bullet_point.nodes.to_markdown()),
+            };
+
+            lines.push(with_block_id(line, &bullet_point.block_id));
+
+            for child in &bullet_point.children {
+                lines.push(child.to_markdown());
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl ToMarkdown for document::block::table::Table {
+    fn to_markdown(&self) -> String {
+        use document::block::table::Alignment;
+
+        // Pipes inside of cells have to be escaped, including the ones of wiki links
+        let row_to_markdown = |row: &Vec<document::Nodes>| {
+            let cells = row
+                .iter()
+                .map(|cell| cell.to_markdown().replace('|', "\\|"))
+                .collect::<Vec<_>>();
+
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let delimiters = self
+            .alignments
+            .iter()
+            .map(|alignment| match alignment {
+                Some(Alignment::Left) => ":---",
+                Some(Alignment::Center) => ":---:",
+                Some(Alignment::Right) => "---:",
+                None => "---",
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = vec![
+            row_to_markdown(&self.header),
+            format!("| {} |", delimiters.join(" | ")),
+        ];
+
+        lines.extend(self.rows.iter().map(row_to_markdown));
+
+        lines.join("\n")
+    }
+}
+
+impl ToMarkdown for document::Blocks {
+    fn to_markdown(&self) -> String {
+        self.iter()
+            .map(ToMarkdown::to_markdown)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+impl ToMarkdown for document::Document {
+    fn to_markdown(&self) -> String {
+        let mut markdown = self.blocks().to_markdown();
+
+        if !markdown.is_empty() {
+            markdown.push('\n');
+        }
+
+        markdown
+    }
+}
+
+fn element_to_yaml(element: &header::Element, is_tag_line: bool) -> yaml_rust2::Yaml {
+    use header::{Element, Time};
+    use yaml_rust2::Yaml;
+
+    match element {
+        Element::String(text) => Yaml::String(text.clone()),
+        // Obsidian writes tags in the frontmatter without the leading `#`
+        Element::Tag(tag) if is_tag_line => Yaml::String(tag.clone()),
+        Element::Tag(tag) => Yaml::String(format!("#{}", tag)),
+        Element::Reference(link::reference::Reference::Entity(id)) => Yaml::String(format!("[[{}]]", id.id())),
+        Element::Reference(link::reference::Reference::Url(url)) => Yaml::String(url.to_string()),
+        Element::Time(Time::Date(date)) => Yaml::String(date.format("%Y-%m-%d").to_string()),
+        Element::Time(Time::DateTime(datetime)) => Yaml::String(datetime.format("%Y-%m-%d %H:%M").to_string()),
+        Element::Boolean(value) => Yaml::Boolean(*value),
+    }
+}
+
+fn line_to_yaml(key: &str, line: &header::Line) -> yaml_rust2::Yaml {
+    let is_tag_line = key == "tags" || key == "tag";
+
+    match line {
+        header::Line::Single(element) => element_to_yaml(element, is_tag_line),
+        header::Line::Multi(elements) => yaml_rust2::Yaml::Array(
+            elements
+                .iter()
+                .map(|element| element_to_yaml(element, is_tag_line))
+                .collect(),
+        ),
+    }
+}
+
+/// Build the YAML frontmatter from the parsed lines. Values we don't understand are taken
+/// over from the original YAML, so that nothing gets lost when writing the file back.
+fn header_to_yaml(header: &header::Header) -> Option<yaml_rust2::Yaml> {
+    use yaml_rust2::Yaml;
+
+    let mut hash = yaml_rust2::yaml::Hash::new();
+
+    let value_for = |key: &str| {
+        if key == "title" {
+            if let Some(title) = &header.title {
+                return Some(Yaml::String(title.clone()));
+            }
+        }
+
+        header.lines
+            .iter()
+            .find(|(line_key, _)| line_key == key)
+            .map(|(_, line)| line_to_yaml(key, line))
+    };
+
+    // Keep the order of the original frontmatter
+    if let Some(Yaml::Hash(original)) = &header.yaml {
+        for (key, value) in original {
+            let value = key
+                .as_str()
+                .and_then(value_for)
+                .unwrap_or_else(|| value.clone());
+
+            hash.insert(key.clone(), value);
+        }
+    }
+
+    if let Some(title) = &header.title {
+        let key = Yaml::String("title".to_string());
+        if !hash.contains_key(&key) {
+            hash.insert(key, Yaml::String(title.clone()));
+        }
+    }
+
+    for (key, line) in &header.lines {
+        let yaml_key = Yaml::String(key.clone());
+        if !hash.contains_key(&yaml_key) {
+            hash.insert(yaml_key, line_to_yaml(key, line));
+        }
+    }
+
+    if hash.is_empty() {
+        None
+    } else {
+        Some(Yaml::Hash(hash))
+    }
+}
+
+impl ToMarkdown for header::Header {
+    /// The frontmatter including its `---` delimiters, or nothing if there is no metadata.
+    fn to_markdown(&self) -> String {
+        let yaml = match header_to_yaml(self) {
+            Some(yaml) => yaml,
+            None => return String::new(),
+        };
+
+        let mut dumped = String::new();
+
+        if yaml_rust2::YamlEmitter::new(&mut dumped).dump(&yaml).is_err() {
+            return String::new();
+        }
+
+        // The emitter starts the document with `---` on its own
+        format!("{}\n---\n", dumped.trim_end())
+    }
+}
+
+impl ToMarkdown for zettel::Zettel {
+    fn to_markdown(&self) -> String {
+        let frontmatter = self.header.to_markdown();
+
+        let body = match self.body_as_document() {
+            Some(document) => document.to_markdown(),
+            None => String::new(),
+        };
+
+        if frontmatter.is_empty() {
+            body
+        } else if body.is_empty() {
+            frontmatter
+        } else {
+            format!("{}\n{}", frontmatter, body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToMarkdown;
+    use crate::core::entity::zettel;
+    use crate::formats::markdown::testing::{parse_zettel as parse, without_positions};
+
+    /// The document without the positions in the source, which move when the frontmatter gets reformatted.
+    fn content_of(zettel: &zettel::Zettel) -> serde_json::Value {
+        without_positions(&zettel.body_as_document())
+    }
+
+    /// Serializing and parsing again must not change anything, and the output must be stable.
+    fn assert_round_trip(source: &str) {
+        let original = parse(source);
+        let markdown = original.to_markdown();
+        let reparsed = parse(&markdown);
+
+        assert_eq!(content_of(&original), content_of(&reparsed), "{}", markdown);
+        assert_eq!(original.header.title, reparsed.header.title, "{}", markdown);
+        assert_eq!(original.header.lines, reparsed.header.lines, "{}", markdown);
+        assert_eq!(markdown, reparsed.to_markdown());
+    }
+
+    #[test]
+    fn round_trip_example_vault() {
+        let example = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("example");
+
+        let mut count = 0;
+
+        for entry in std::fs::read_dir(example).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|extension| extension == "md") {
+                assert_round_trip(&std::fs::read_to_string(&path).unwrap());
+                count += 1;
+            }
+        }
+
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn round_trip_inline_elements() {
+        assert_round_trip("Some **bold *and italic*** text with `code` and a #nested/tag.\n");
+        assert_round_trip("A line \\\nbreak and https://example.com/path?q=1 in between.\n");
+        assert_round_trip("[[target]] and [[target|a **caption**]] and ![[image.png]]\n");
+        assert_round_trip("[a link](https://example.com/) and ![an image](https://example.com/a.png)\n");
+    }
+
+    #[test]
+    fn round_trip_blocks() {
+        assert_round_trip("# Title\n\nParagraph\nwith two lines.\n\n---\n\n- one\n- [ ] two\n- [ ] three\n\n```rust\nfn main() {}\n\n// done\n```\n");
+        assert_round_trip("> [!note]\n> # Inside\n> Text\n>\n> > Nested\n\n> Basic callout\n");
+    }
+
+    #[test]
+    fn round_trip_lists() {
+        assert_round_trip("- one\n\t- nested\n\t\t1. deep\n\t\t2. deeper\n- two\n\n3. three\n4. [ ] four\n");
+        assert_round_trip("> - in a callout\n>   - nested\n");
+    }
+
+    #[test]
+    fn round_trip_tasks() {
+        assert_round_trip("- [ ] open\n- [x] done\n- [-] cancelled\n- [/] in progress 📅 2026-10-20 ⏫\n");
+    }
+
+    #[test]
+    fn round_trip_tables() {
+        assert_round_trip("| Name | Link | Size |\n| :--- | :---: | ---: |\n| a | [[c|the c]] | 1 |\n| `x \\| y` | #tag |  |\n");
+        assert_round_trip("Text before\n| a | b |\n|---|---|\n| 1 |\n\nAfter\n");
+    }
+
+    #[test]
+    fn round_trip_frontmatter() {
+        assert_round_trip("---\ntitle: \"Foo: Bar\"\ntags: [a, b/c]\nsee: \"[[other]]\"\nwhen: 2024-01-31\ndone: true\n---\n\nBody\n");
+        assert_round_trip("---\ntags: single\n---\n\nBody\n");
+    }
+
+    #[test]
+    fn frontmatter_keeps_unknown_values() {
+        let zettel = parse("---\ntitle: Foo\ncount: 3\nnested:\n  a: 1\n---\nBody\n");
+        let markdown = zettel.to_markdown();
+
+        assert!(markdown.starts_with("---\ntitle: Foo\ncount: 3\nnested:\n  a: 1\n---\n"), "{}", markdown);
+    }
+
+    #[test]
+    fn round_trip_anchors() {
+        assert_round_trip("See [[c#Hello, world!]] and ![[c#^abc-1|the block]]. ^para\n\n- item ^item\n- [ ] task ^task\n");
+    }
+
+    #[test]
+    fn thematic_break_is_not_frontmatter() {
+        let source = "Before\n\n---\n\nBetween\n\n---\n\nAfter\n";
+
+        assert_eq!(parse(source).body_as_document().unwrap().blocks().len(), 5);
+        assert_round_trip(source);
+    }
+
+    #[test]
+    fn round_trip_callouts() {
+        assert_round_trip("> [!tip]- A **bold** title with [[c]]\n> Hidden text\n\n> [!warning]+\n> Open\n\n> [!my-kind] Custom\n> Text\n");
+    }
+    #[test]
+    fn round_trip_extended_inline_syntax() {
+        assert_round_trip("a ~~gone **bold**~~ ==*marked*== and [[c|==x==]]\n\n$x^2$ %%hidden%% \\*not\\* \\[x\\]\n");
+        assert_round_trip("Before\n%%\nhidden\n\n- [ ] not a task\n%%\n\nAfter\n");
+    }
+}
@@ -1,4 +1,6 @@
+use crate::core::entity::link;
 use crate::semantic;
+use crate::util::span::Spanned;
 
 
 pub mod conversions;
@@ -7,8 +9,8 @@ pub mod block;
 pub mod node;
 
 
-pub type Blocks = Vec<block::Block>;
-pub type Nodes = Vec<node::Node>;
+pub type Blocks = Vec<Spanned<block::Block>>;
+pub type Nodes = Vec<Spanned<node::Node>>;
 
 impl semantic::Scannable for Blocks {
     fn iterate_info_items<F: FnMut(semantic::InfoItem)>(&self, func: &mut F) {
@@ -27,7 +29,7 @@ impl semantic::Scannable for Nodes {
 }
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 pub struct Document {
     blocks: Blocks,
 }
@@ -36,6 +38,107 @@ impl Document {
     pub fn with_blocks(blocks: Blocks) -> Self {
         Document { blocks }
     }
+
+    pub fn blocks(&self) -> &Blocks {
+        &self.blocks
+    }
+
+    /// The text of the first top-level heading, which Obsidian shows when there is no title.
+    pub fn title(&self) -> Option<String> {
+        self.blocks.iter().find_map(|block| match &block.value {
+            block::Block::Heading(heading) if heading.level == 1 => {
+                let text = node::nodes_to_plain_text(&heading.nodes).trim().to_string();
+                (!text.is_empty()).then_some(text)
+            }
+            _ => None,
+        })
+    }
+
+    /// The bullet point written in the given line of the source, also looking into callouts and nested lists.
+    pub fn bullet_point_at_line(&self, line: usize) -> Option<&block::bullet_point::BulletPoint> {
+        fn in_list(list: &block::list::List, line: usize) -> Option<&block::bullet_point::BulletPoint> {
+            list.items.iter().find_map(|bullet_point| {
+                if bullet_point.span.start.line == line {
+                    Some(bullet_point)
+                } else {
+                    bullet_point.children.iter().find_map(|child| in_list(child, line))
+                }
+            })
+        }
+
+        fn in_blocks(blocks: &Blocks, line: usize) -> Option<&block::bullet_point::BulletPoint> {
+            blocks.iter().find_map(|block| match &block.value {
+                block::Block::List(list) => in_list(list, line),
+                block::Block::Callout(callout) => in_blocks(&callout.blocks, line),
+                _ => None,
+            })
+        }
+
+        in_blocks(&self.blocks, line)
+    }
+
+    /// The part of the document an anchor points to: a heading with everything up to the
+    /// next heading of the same or a higher level, or a single block.
+    pub fn section(&self, anchor: &link::reference::Anchor) -> Option<Document> {
+        match anchor {
+            link::reference::Anchor::Heading(_) => {
+                let id = anchor.html_id();
+
+                let (start, level) = self.blocks.iter().enumerate().find_map(|(index, block)| match &block.value {
+                    block::Block::Heading(heading) if heading.html_id() == id => Some((index, heading.level)),
+                    _ => None,
+                })?;
+
+                let blocks = self.blocks[start..]
+                    .iter()
+                    .enumerate()
+                    .take_while(|(index, block)| {
+                        *index == 0 || !matches!(&block.value, block::Block::Heading(heading) if heading.level <= level)
+                    })
+                    .map(|(_, block)| block.clone())
+                    .collect();
+
+                Some(Document::with_blocks(blocks))
+            }
+            link::reference::Anchor::Block(id) => {
+                find_block(&self.blocks, id).map(|block| Document::with_blocks(vec![block]))
+            }
+        }
+    }
+}
+
+/// The block marked with the given id, looking into callouts and lists. Bullet points are
+/// returned as a list of their own.
+fn find_block(blocks: &Blocks, id: &str) -> Option<Spanned<block::Block>> {
+    fn in_list(list: &block::list::List, id: &str) -> Option<Spanned<block::Block>> {
+        list.items.iter().enumerate().find_map(|(index, bullet_point)| {
+            if bullet_point.block_id.as_deref() == Some(id) {
+                // An ordered list keeps the number of the item
+                let kind = match list.kind {
+                    block::list::Kind::Ordered { start } => block::list::Kind::Ordered { start: start + index as u64 },
+                    kind => kind,
+                };
+
+                Some(Spanned::new(
+                    block::Block::List(block::list::List {
+                        kind,
+                        depth: list.depth,
+                        items: vec![bullet_point.clone()],
+                    }),
+                    bullet_point.span,
+                ))
+            } else {
+                bullet_point.children.iter().find_map(|child| in_list(child, id))
+            }
+        })
+    }
+
+    blocks.iter().find_map(|block| match &block.value {
+        block::Block::Paragraph(paragraph) if paragraph.block_id.as_deref() == Some(id) => Some(block.clone()),
+        block::Block::List(list) => in_list(list, id),
+        block::Block::Callout(callout) => find_block(&callout.blocks, id),
+        _ => None,
+    })
 }
 
 impl semantic::Scannable for Document {
@@ -45,3 +148,35 @@ impl semantic::Scannable for Document {
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use crate::core::entity::{link::reference::Anchor, zettel::document::conversions::to_markdown::ToMarkdown};
+    use crate::formats::markdown::testing::parse_zettel;
+
+    #[test]
+    fn sections_of_anchors() {
+        let zettel = parse_zettel("# Top\n\nIntro\n\n## Part one\n\nText\n\n### Detail\n\nMore\n\n## Part two\n\n1. a\n2. b ^second\n");
+        let document = zettel.body_as_document().unwrap();
+
+        let section = document.section(&Anchor::Heading("Top#Part One".to_string())).unwrap();
+        assert_eq!(section.blocks().len(), 4);
+        assert_eq!(section.blocks().to_markdown(), "## Part one\n\nText\n\n### Detail\n\nMore");
+
+        let section = document.section(&Anchor::Block("second".to_string())).unwrap();
+        assert_eq!(section.blocks().to_markdown(), "2. b ^second");
+
+        assert!(document.section(&Anchor::Heading("Missing".to_string())).is_none());
+    }
+
+    #[test]
+    fn title_falls_back_to_first_heading() {
+        let zettel = parse_zettel("---\naliases: [First]\n---\nIntro\n\n## Sub\n\n# The *real* title\n\n# Another\n");
+
+        assert_eq!(zettel.title(), None);
+        assert_eq!(zettel.body_as_document().unwrap().title().as_deref(), Some("The real title"));
+
+        assert_eq!(parse_zettel("## Only a subheading\n").body_as_document().unwrap().title(), None);
+    }
+}
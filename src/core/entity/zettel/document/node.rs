@@ -4,36 +4,69 @@ use crate::semantic;
 use super::Nodes;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Style {
     Bold,
     Italic,
     Underline,
     Strikethrough,
+    Highlight,
 }
 
 pub mod reference {
     use crate::core::entity::link::reference;
     use super::Nodes;
 
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
     pub struct Reference {
         pub target: reference::Reference,
+        /// The heading or block inside of the target.
+        pub anchor: Option<reference::Anchor>,
         pub caption: Nodes,
         pub embed: bool,
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
 pub enum Node {
     Newline,
     Text(String),
     Tag(String),
     Code(String),
+    /// An inline formula in TeX notation.
+    Math(String),
+    /// Text that is kept in the source, but never shown.
+    Comment(String),
+    /// A character that was escaped with a backslash.
+    Escaped(char),
     Styled(Style, Box<Node>),
     Reference(reference::Reference),
     Grouped(Nodes),
 }
 
+impl Node {
+    /// Render the node as unformatted text, e.g. for excerpts and indexing.
+    pub fn to_plain_text(&self) -> String {
+        match self {
+            Node::Newline => " ".to_string(),
+            Node::Text(text) => text.clone(),
+            Node::Tag(tag) => format!("#{}", tag),
+            Node::Code(code) => code.clone(),
+            Node::Math(tex) => tex.clone(),
+            Node::Comment(_) => String::new(),
+            Node::Escaped(c) => c.to_string(),
+            Node::Styled(_, node) => node.to_plain_text(),
+            Node::Reference(reference) => nodes_to_plain_text(&reference.caption),
+            Node::Grouped(nodes) => nodes_to_plain_text(nodes),
+        }
+    }
+}
+
+pub fn nodes_to_plain_text(nodes: &Nodes) -> String {
+    nodes.iter().map(|node| node.to_plain_text()).collect()
+}
+
 impl semantic::Scannable for Node {
     fn iterate_info_items<F: FnMut(semantic::InfoItem)>(&self, func: &mut F) {
         match self {
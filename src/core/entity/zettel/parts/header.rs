@@ -3,13 +3,15 @@ use crate::{
     semantic,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
 pub enum Time {
     DateTime(chrono::NaiveDateTime),
     Date(chrono::NaiveDate),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
 pub enum Element {
     String(String),
     Tag(String),
@@ -78,6 +80,19 @@ impl Element {
         }
     }
 
+    /// The text a user would read for this element, if there is any.
+    pub fn to_plain_text(&self) -> Option<String> {
+        match self {
+            Element::String(s) => Some(s.clone()),
+            Element::Tag(tag) => Some(tag.clone()),
+            Element::Reference(Reference::Entity(id)) => Some(id.id()),
+            Element::Reference(Reference::Url(url)) => Some(url.to_string()),
+            Element::Time(Time::Date(date)) => Some(date.to_string()),
+            Element::Time(Time::DateTime(datetime)) => Some(datetime.to_string()),
+            Element::Boolean(_) => None,
+        }
+    }
+
     pub fn from_yaml(yaml: &yaml_rust2::Yaml) -> Option<Self> {
         match yaml {
             yaml_rust2::Yaml::String(s) => Some(Self::parse_string(s)),
@@ -102,7 +117,8 @@ impl semantic::Scannable for Element {
 }
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(untagged)]
 pub enum Line {
     Single(Element),
     Multi(Vec<Element>),
@@ -110,12 +126,49 @@ pub enum Line {
 
 impl Line {
     pub fn from_yaml(yaml: &yaml_rust2::Yaml) -> Option<Self> {
+        Self::from_yaml_with(yaml, Element::from_yaml)
+    }
+
+    /// Parse the value of a `tags:` line. Obsidian writes these without the leading `#`,
+    /// either as a list or as a single string separated by commas or spaces.
+    pub fn tags_from_yaml(yaml: &yaml_rust2::Yaml) -> Option<Self> {
+        match yaml {
+            yaml_rust2::Yaml::String(s) => {
+                let tags = s
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .map(|tag| tag.trim_start_matches('#'))
+                    .filter(|tag| !tag.is_empty())
+                    .map(|tag| Element::Tag(tag.to_string()))
+                    .collect::<Vec<_>>();
+
+                match tags.len() {
+                    1 => tags.into_iter().next().map(Line::Single),
+                    _ => Some(Line::Multi(tags)),
+                }
+            }
+            _ => Self::from_yaml_with(yaml, |yaml| {
+                yaml.as_str()
+                    .map(|tag| Element::Tag(tag.trim_start_matches('#').to_string()))
+            }),
+        }
+    }
+
+    fn from_yaml_with(yaml: &yaml_rust2::Yaml, element_func: fn(&yaml_rust2::Yaml) -> Option<Element>) -> Option<Self> {
         match yaml {
             yaml_rust2::Yaml::Array(arr) => {
-                let elements = arr.iter().filter_map(Element::from_yaml).collect();
+                let elements = arr.iter().filter_map(element_func).collect();
                 Some(Line::Multi(elements))
             }
-            _ => Some(Line::Single(Element::from_yaml(yaml)?)),
+            _ => Some(Line::Single(element_func(yaml)?)),
+        }
+    }
+}
+
+impl Line {
+    pub fn elements(&self) -> &[Element] {
+        match self {
+            Line::Single(element) => std::slice::from_ref(element),
+            Line::Multi(elements) => elements,
         }
     }
 }
@@ -158,7 +211,10 @@ impl Header {
                     .iter()
                     .filter_map(|(k, v)| {
                         let key = k.as_str()?;
-                        let value = Line::from_yaml(v)?;
+                        let value = match key {
+                            "tags" | "tag" => Line::tags_from_yaml(v)?,
+                            _ => Line::from_yaml(v)?,
+                        };
                         Some((key.to_string(), value))
                     })
                     .collect::<Vec<_>>();
@@ -174,6 +230,16 @@ impl Header {
             yaml: Some(yaml.clone()),
         }
     }
+
+    /// The other names of the note from `aliases:`, links can use them instead of the file name.
+    pub fn aliases(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter(|(key, _)| key == "aliases" || key == "alias")
+            .flat_map(|(_, line)| line.elements())
+            .filter_map(Element::to_plain_text)
+            .collect()
+    }
 }
 
 impl Default for Header {
@@ -189,3 +255,16 @@ impl semantic::Scannable for Header {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::markdown::testing::parse_zettel;
+
+    #[test]
+    fn aliases_from_lists_and_single_values() {
+        let zettel = parse_zettel("---\naliases: [First, \"Second one\"]\nalias: Third\ntitle: Not an alias\n---\nBody\n");
+        assert_eq!(zettel.header.aliases(), vec!["First", "Second one", "Third"]);
+
+        assert!(parse_zettel("No frontmatter\n").header.aliases().is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::core::entity;
+
+/// Aliases are compared like the names in links, case-insensitively.
+fn normalize_alias(alias: &str) -> String {
+    alias.trim().to_lowercase()
+}
+
+/// Maps the `aliases:` of the notes to the entities carrying them.
+pub struct AliasIndex {
+    aliased: HashMap<String, Vec<entity::Id>>,
+    by_source: HashMap<entity::Id, Vec<String>>,
+}
+
+impl AliasIndex {
+    pub fn new() -> Self {
+        Self {
+            aliased: HashMap::new(),
+            by_source: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, source: &entity::Id, alias: &str) {
+        let alias = normalize_alias(alias);
+
+        if alias.is_empty() {
+            return;
+        }
+
+        let entities = self.aliased.entry(alias.clone()).or_default();
+
+        if !entities.contains(source) {
+            entities.push(source.clone());
+            self.by_source.entry(source.clone()).or_default().push(alias);
+        }
+    }
+
+    pub fn remove_source(&mut self, source: &entity::Id) {
+        for alias in self.by_source.remove(source).into_iter().flatten() {
+            if let Some(entities) = self.aliased.get_mut(&alias) {
+                entities.retain(|entity| entity != source);
+
+                if entities.is_empty() {
+                    self.aliased.remove(&alias);
+                }
+            }
+        }
+    }
+
+    /// The entities using the alias, usually only one.
+    pub fn entities(&self, alias: &str) -> Vec<entity::Id> {
+        self.aliased
+            .get(&normalize_alias(alias))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl Default for AliasIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AliasIndex;
+    use crate::core::entity::Id;
+
+    fn id(name: &str) -> Id {
+        Id::Basic(name.to_string())
+    }
+
+    #[test]
+    fn aliases_are_case_insensitive_and_shared() {
+        let mut index = AliasIndex::new();
+
+        index.add(&id("a"), " The Alias ");
+        index.add(&id("a"), "the alias");
+        index.add(&id("b"), "THE ALIAS");
+        index.add(&id("b"), "  ");
+
+        assert_eq!(index.entities("the alias"), vec![id("a"), id("b")]);
+        assert!(index.entities("").is_empty());
+
+        index.remove_source(&id("a"));
+        assert_eq!(index.entities("The Alias"), vec![id("b")]);
+
+        index.remove_source(&id("b"));
+        assert!(index.entities("the alias").is_empty());
+        assert!(index.aliased.is_empty() && index.by_source.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::entity;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Backlink {
+    pub source: entity::Id,
+    pub contexts: Vec<String>,
+}
+
+impl Backlink {
+    fn new(source: entity::Id) -> Self {
+        Self {
+            source,
+            contexts: Vec::new(),
+        }
+    }
+}
+
+/// Reverse index of all references in the vault, mapping each target to the entities referring to it.
+pub struct BacklinkIndex {
+    incoming: HashMap<entity::Id, Vec<Backlink>>,
+    outgoing: HashMap<entity::Id, HashSet<entity::Id>>,
+}
+
+impl BacklinkIndex {
+    pub fn new() -> Self {
+        Self {
+            incoming: HashMap::new(),
+            outgoing: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, source: &entity::Id, target: &entity::Id, context: Option<String>) {
+        self.outgoing
+            .entry(source.clone())
+            .or_default()
+            .insert(target.clone());
+
+        let backlinks = self.incoming.entry(target.clone()).or_default();
+
+        let backlink = match backlinks.iter_mut().position(|b| &b.source == source) {
+            Some(index) => &mut backlinks[index],
+            None => {
+                backlinks.push(Backlink::new(source.clone()));
+                backlinks.last_mut().unwrap()
+            }
+        };
+
+        if let Some(context) = context {
+            if !backlink.contexts.contains(&context) {
+                backlink.contexts.push(context);
+            }
+        }
+    }
+
+    /// Drop every reference that originates from the given entity, e.g. because it was modified.
+    pub fn remove_source(&mut self, source: &entity::Id) {
+        for target in self.outgoing.remove(source).into_iter().flatten() {
+            if let Some(backlinks) = self.incoming.get_mut(&target) {
+                backlinks.retain(|b| &b.source != source);
+
+                if backlinks.is_empty() {
+                    self.incoming.remove(&target);
+                }
+            }
+        }
+    }
+
+    pub fn backlinks(&self, target: &entity::Id) -> &[Backlink] {
+        self.incoming
+            .get(target)
+            .map(|backlinks| backlinks.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+impl Default for BacklinkIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
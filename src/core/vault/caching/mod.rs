@@ -1,8 +1,12 @@
-use crate::{core::entity, util::hashing};
+use crate::{core::entity, search, util::hashing};
 
 use super::volume;
 
+pub mod aliases;
+pub mod backlinks;
 pub mod storage;
+pub mod tags;
+pub mod threads;
 
 pub mod caches {
     pub mod by_path {
@@ -59,6 +63,10 @@ pub mod caches {
                 self.paths.insert(path);
             }
 
+            pub fn remove_path(&mut self, path: &volume::VolumePath) {
+                self.paths.remove(path);
+            }
+
             pub fn paths(&self) -> impl std::iter::Iterator<Item = &volume::VolumePath> {
                 self.paths.iter()
             }
@@ -112,11 +120,25 @@ pub mod caches {
 #[derive(serde::Serialize, serde::Deserialize)]
 struct GlobalCacheSaveState {}
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CacheStats {
+    pub by_id: storage::StorageStats,
+    pub by_path: storage::StorageStats,
+    pub by_sha256: storage::StorageStats,
+    pub search_documents: usize,
+    pub search_terms: usize,
+}
+
 pub struct GlobalCache {
     base_path: std::path::PathBuf,
     by_id: storage::DataStorage<caches::by_id::Metadata>,
     by_path: storage::DataStorage<caches::by_path::Metadata>,
     by_sha256: storage::DataStorage<caches::by_sha256::Metadata>,
+    backlinks: Option<backlinks::BacklinkIndex>,
+    tags: Option<tags::TagIndex>,
+    aliases: Option<aliases::AliasIndex>,
+    threads: Option<threads::ThreadIndex>,
+    search: search::InvertedIndex,
 }
 
 impl GlobalCache {
@@ -124,12 +146,18 @@ impl GlobalCache {
         let by_id = base_path.join("by_id");
         let by_path = base_path.join("by_path");
         let by_sha256 = base_path.join("by_sha256");
+        let search = search::InvertedIndex::open(&base_path.join("search"));
 
         Self {
             base_path,
             by_id: storage::DataStorage::open(by_id, true).unwrap(),
             by_path: storage::DataStorage::open(by_path, true).unwrap(),
             by_sha256: storage::DataStorage::open(by_sha256, true).unwrap(),
+            backlinks: None,
+            tags: None,
+            aliases: None,
+            threads: None,
+            search,
         }
     }
 
@@ -169,6 +197,37 @@ impl GlobalCache {
         self.report_hash_2(&hash, &path); // Tell the system the hash of the file
     }
 
+    /// Forget everything we know about the content at the given path.
+    /// This has to be called whenever the file changes, otherwise stale hashes are reported.
+    pub fn invalidate_path(&mut self, path: &volume::VolumePath) {
+        if let Some(old_hash) = self.get_hash(path) {
+            self.by_sha256
+                .modify(old_hash, |metadata| {
+                    metadata.remove_path(path);
+                })
+                .map_err(|_| {
+                    eprintln!("Failed to unlink old hash for path: {:?}", path);
+                })
+                .ok();
+        }
+
+        self.by_path
+            .purge(path.as_hash())
+            .map_err(|_| {
+                eprintln!("Failed to invalidate cache for path: {:?}", path);
+            })
+            .ok();
+    }
+
+    pub fn invalidate_id(&mut self, id: &entity::Id) {
+        self.by_id
+            .purge(id.clone())
+            .map_err(|_| {
+                eprintln!("Failed to invalidate cache for ID: {:?}", id);
+            })
+            .ok();
+    }
+
     pub fn get_title(&mut self, id: &entity::Id) -> Option<String> {
         self.by_id
             .access(id.clone(), |metadata| metadata.get_title().cloned())
@@ -187,6 +246,180 @@ impl GlobalCache {
             .ok();
     }
 
+    /// The backlink index is only kept in memory. It is `None` until it has been built once.
+    pub fn backlinks(&self) -> Option<&backlinks::BacklinkIndex> {
+        self.backlinks.as_ref()
+    }
+
+    pub fn backlinks_mut(&mut self) -> Option<&mut backlinks::BacklinkIndex> {
+        self.backlinks.as_mut()
+    }
+
+    pub fn set_backlinks(&mut self, index: backlinks::BacklinkIndex) {
+        self.backlinks = Some(index);
+    }
+
+    /// Drop the backlink index, it is rebuilt the next time it is needed.
+    pub fn reset_backlinks(&mut self) {
+        self.backlinks = None;
+    }
+
+    /// Like the backlinks, the tag index is only kept in memory.
+    pub fn tags(&self) -> Option<&tags::TagIndex> {
+        self.tags.as_ref()
+    }
+
+    pub fn tags_mut(&mut self) -> Option<&mut tags::TagIndex> {
+        self.tags.as_mut()
+    }
+
+    pub fn set_tags(&mut self, index: tags::TagIndex) {
+        self.tags = Some(index);
+    }
+
+    /// The aliases are kept in memory as well.
+    pub fn aliases(&self) -> Option<&aliases::AliasIndex> {
+        self.aliases.as_ref()
+    }
+
+    pub fn aliases_mut(&mut self) -> Option<&mut aliases::AliasIndex> {
+        self.aliases.as_mut()
+    }
+
+    pub fn set_aliases(&mut self, index: aliases::AliasIndex) {
+        self.aliases = Some(index);
+    }
+
+    /// The email threads are kept in memory as well.
+    pub fn threads(&self) -> Option<&threads::ThreadIndex> {
+        self.threads.as_ref()
+    }
+
+    pub fn threads_mut(&mut self) -> Option<&mut threads::ThreadIndex> {
+        self.threads.as_mut()
+    }
+
+    pub fn set_threads(&mut self, index: threads::ThreadIndex) {
+        self.threads = Some(index);
+    }
+
+    /// Unlike the backlinks and tags, the search index is persisted in the cache directory.
+    pub fn search_index(&self) -> &search::InvertedIndex {
+        &self.search
+    }
+
+    pub fn search_index_mut(&mut self) -> &mut search::InvertedIndex {
+        &mut self.search
+    }
+
+    /// Forget everything, both in memory and on disk.
+    pub fn clear(&mut self) -> Result<(), std::io::Error> {
+        let failed = || std::io::Error::other("Unable to clear metadata cache");
+
+        self.by_id.clear().map_err(|_| failed())?;
+        self.by_path.clear().map_err(|_| failed())?;
+        self.by_sha256.clear().map_err(|_| failed())?;
+
+        self.backlinks = None;
+        self.tags = None;
+        self.aliases = None;
+        self.threads = None;
+
+        let search_path = self.base_path.join("search");
+
+        if search_path.exists() {
+            std::fs::remove_dir_all(&search_path)?;
+        }
+
+        self.search = search::InvertedIndex::new();
+
+        Ok(())
+    }
+
+    /// Remove all entries that don't belong to one of the given paths or IDs.
+    /// Returns the number of entries that were removed.
+    pub fn collect_garbage(
+        &mut self,
+        paths: &std::collections::HashSet<volume::VolumePath>,
+        ids: &std::collections::HashSet<entity::Id>,
+    ) -> usize {
+        // Everything has to be on disk, otherwise we would miss entries
+        self.save().ok();
+
+        let mut removed = 0;
+
+        let path_hashes = paths.iter().map(|path| path.as_hash()).collect::<std::collections::HashSet<_>>();
+        let id_hashes = ids.iter().map(|id| id.as_hash()).collect::<std::collections::HashSet<_>>();
+
+        for key in self.by_path.keys() {
+            if !path_hashes.contains(&key) && self.by_path.purge(key).is_ok() {
+                removed += 1;
+            }
+        }
+
+        for key in self.by_id.keys() {
+            if !id_hashes.contains(&key) && self.by_id.purge(key).is_ok() {
+                removed += 1;
+            }
+        }
+
+        for key in self.by_sha256.keys() {
+            let stale = self
+                .by_sha256
+                .access(key.clone(), |metadata| {
+                    metadata
+                        .paths()
+                        .filter(|path| !paths.contains(*path))
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            if stale.is_empty() {
+                continue;
+            }
+
+            // Entries without any paths left are deleted when flushing
+            self.by_sha256
+                .modify(key, |metadata| {
+                    for path in &stale {
+                        metadata.remove_path(path);
+                    }
+                })
+                .ok();
+
+            removed += 1;
+        }
+
+        let orphans = self
+            .search
+            .ids()
+            .filter(|id| !ids.contains(*id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for id in orphans {
+            self.search.remove(&id);
+            removed += 1;
+        }
+
+        self.save().ok();
+
+        removed
+    }
+
+    pub fn stats(&mut self) -> CacheStats {
+        self.save().ok();
+
+        CacheStats {
+            by_id: self.by_id.stats(),
+            by_path: self.by_path.stats(),
+            by_sha256: self.by_sha256.stats(),
+            search_documents: self.search.document_count(),
+            search_terms: self.search.term_count(),
+        }
+    }
+
     pub fn save(&mut self) -> Result<(), std::io::Error> {
         let snapshot = GlobalCacheSaveState {};
 
@@ -218,6 +451,13 @@ impl GlobalCache {
             })
             .ok();
 
+        self.search
+            .save(&self.base_path.join("search"))
+            .map_err(|_| {
+                eprintln!("Failed to save search index");
+            })
+            .ok();
+
         Ok(())
     }
 }
@@ -13,6 +13,13 @@ pub struct EntryInfo {
     pub dirty: bool,
 }
 
+/// How much space a storage takes up on disk.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub struct StorageStats {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
 pub struct DataStorage<T>
 where
     T: serde::Serialize + for<'de> serde::Deserialize<'de> + Default + Stored,
@@ -92,8 +99,6 @@ where
 
     fn delete(&mut self, key: &Sha256) -> Result<(), ()> {
         // Delete from the cache and the file system
-        
-        println!("Deleting data for key {}", key.as_string());
 
         let path = self.local_path_for_key(key);
 
@@ -137,18 +142,16 @@ where
         F: FnOnce(&mut T),
     {
         let key = key.into();
-        let mut data = self.read(&key).map_err(|_| ())?;
-        
-        f(&mut data);
+
+        // Go through the cache, otherwise unflushed modifications would be lost
+        f(self.get(&key)?);
 
         // Mark the entry as dirty
         self.entry_info
-            .entry(key.clone())
+            .entry(key)
             .or_insert_with(|| EntryInfo { dirty: false })
             .dirty = true;
 
-        self.cached.insert(key, data);
-
         Ok(())
     }
 
@@ -160,6 +163,47 @@ where
         self.delete(&key)
     }
 
+    fn stored_files(&self) -> impl Iterator<Item = walkdir::DirEntry> {
+        walkdir::WalkDir::new(&self.base_path)
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "json"))
+    }
+
+    /// All keys that have an entry on disk. Unflushed entries are not included.
+    pub fn keys(&self) -> Vec<Sha256> {
+        self.stored_files()
+            .filter_map(|entry| {
+                let stem = entry.path().file_stem()?.to_string_lossy().to_string();
+                Sha256::from_string(&stem).ok()
+            })
+            .collect()
+    }
+
+    pub fn stats(&self) -> StorageStats {
+        self.stored_files()
+            .filter_map(|entry| entry.metadata().ok())
+            .fold(StorageStats::default(), |stats, metadata| StorageStats {
+                entries: stats.entries + 1,
+                bytes: stats.bytes + metadata.len(),
+            })
+    }
+
+    /// Throw away all entries, including the ones that have not been flushed yet.
+    pub fn clear(&mut self) -> Result<(), ()> {
+        self.cached.clear();
+        self.entry_info.clear();
+
+        if self.base_path.exists() {
+            std::fs::remove_dir_all(&self.base_path).map_err(|_| ())?;
+        }
+
+        std::fs::create_dir_all(&self.base_path).map_err(|_| ())
+    }
+
     pub fn flush_cache(&mut self) -> Result<(), ()> {
         let mut to_delete = Vec::new();
         
@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::core::entity;
+
+/// Normalize a tag the way Obsidian compares them: case-insensitive and without the leading `#`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim_start_matches('#').trim_matches('/').to_lowercase()
+}
+
+/// All tags implied by a nested tag, e.g. `project/athena` yields `project` and `project/athena`.
+fn expand_tag(tag: &str) -> Vec<String> {
+    let mut expanded = Vec::new();
+
+    for (index, c) in tag.char_indices() {
+        if c == '/' {
+            expanded.push(tag[..index].to_string());
+        }
+    }
+
+    expanded.push(tag.to_string());
+    expanded
+}
+
+/// Maps each tag to the entities carrying it. Nested tags are also filed under their parents.
+pub struct TagIndex {
+    tagged: BTreeMap<String, HashSet<entity::Id>>,
+    by_source: HashMap<entity::Id, HashSet<String>>,
+}
+
+impl TagIndex {
+    pub fn new() -> Self {
+        Self {
+            tagged: BTreeMap::new(),
+            by_source: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, source: &entity::Id, tag: &str) {
+        let tag = normalize_tag(tag);
+
+        if tag.is_empty() {
+            return;
+        }
+
+        for tag in expand_tag(&tag) {
+            self.by_source
+                .entry(source.clone())
+                .or_default()
+                .insert(tag.clone());
+            self.tagged.entry(tag).or_default().insert(source.clone());
+        }
+    }
+
+    pub fn remove_source(&mut self, source: &entity::Id) {
+        for tag in self.by_source.remove(source).into_iter().flatten() {
+            if let Some(entities) = self.tagged.get_mut(&tag) {
+                entities.remove(source);
+
+                if entities.is_empty() {
+                    self.tagged.remove(&tag);
+                }
+            }
+        }
+    }
+
+    pub fn entities(&self, tag: &str) -> Vec<entity::Id> {
+        self.tagged
+            .get(&normalize_tag(tag))
+            .map(|entities| entities.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// All known tags in alphabetical order, together with the number of entities carrying them.
+    pub fn tags(&self) -> Vec<(String, usize)> {
+        self.tagged
+            .iter()
+            .map(|(tag, entities)| (tag.clone(), entities.len()))
+            .collect()
+    }
+
+    /// The direct children of a nested tag, e.g. `project/athena` for `project`.
+    pub fn subtags(&self, tag: &str) -> Vec<(String, usize)> {
+        let prefix = format!("{}/", normalize_tag(tag));
+
+        self.tagged
+            .range(prefix.clone()..)
+            .take_while(|(t, _)| t.starts_with(&prefix))
+            .filter(|(t, _)| !t[prefix.len()..].contains('/'))
+            .map(|(t, entities)| (t.clone(), entities.len()))
+            .collect()
+    }
+}
+
+impl Default for TagIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use crate::core::entity;
+
+/// The headers of an email that are relevant for threading.
+#[derive(Debug, Clone)]
+pub struct ThreadEntry {
+    pub message_id: Option<String>,
+    pub in_reply_to: Vec<String>,
+    pub references: Vec<String>,
+    pub date: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+impl ThreadEntry {
+    /// All message IDs this email is connected to, including its own.
+    fn related_message_ids(&self) -> impl Iterator<Item = &String> {
+        self.message_id
+            .iter()
+            .chain(self.in_reply_to.iter())
+            .chain(self.references.iter())
+    }
+}
+
+/// Groups emails into conversations based on their `Message-ID`, `In-Reply-To` and `References` headers.
+///
+/// Two emails belong to the same thread if they are connected through any chain of these headers,
+/// even if some of the messages in between are not part of the vault.
+pub struct ThreadIndex {
+    entries: HashMap<entity::Id, ThreadEntry>,
+    by_message_id: HashMap<String, entity::Id>,
+    /// The emails mentioning a message ID in any of their threading headers.
+    referrers: HashMap<String, Vec<entity::Id>>,
+}
+
+impl ThreadIndex {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            by_message_id: HashMap::new(),
+            referrers: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, id: &entity::Id, entry: ThreadEntry) {
+        self.remove(id);
+
+        if let Some(message_id) = &entry.message_id {
+            self.by_message_id.insert(message_id.clone(), id.clone());
+        }
+
+        for message_id in entry.related_message_ids() {
+            let referrers = self.referrers.entry(message_id.clone()).or_default();
+
+            if !referrers.contains(id) {
+                referrers.push(id.clone());
+            }
+        }
+
+        self.entries.insert(id.clone(), entry);
+    }
+
+    pub fn remove(&mut self, id: &entity::Id) {
+        if let Some(entry) = self.entries.remove(id) {
+            for message_id in entry.related_message_ids() {
+                if let Some(referrers) = self.referrers.get_mut(message_id) {
+                    referrers.retain(|referrer| referrer != id);
+
+                    if referrers.is_empty() {
+                        self.referrers.remove(message_id);
+                    }
+                }
+            }
+
+            if let Some(message_id) = entry.message_id {
+                if self.by_message_id.get(&message_id) == Some(id) {
+                    self.by_message_id.remove(&message_id);
+                }
+            }
+        }
+    }
+
+    /// The email the given email is a direct reply to, if it is known.
+    pub fn parent(&self, id: &entity::Id) -> Option<entity::Id> {
+        let entry = self.entries.get(id)?;
+
+        // `In-Reply-To` is the most precise, otherwise the last known entry of `References` is the closest ancestor
+        entry
+            .in_reply_to
+            .iter()
+            .chain(entry.references.iter().rev())
+            .filter_map(|message_id| self.by_message_id.get(message_id))
+            .find(|parent| *parent != id)
+            .cloned()
+    }
+
+    /// All known emails that are direct replies to the given email, sorted by date.
+    pub fn replies(&self, id: &entity::Id) -> Vec<entity::Id> {
+        let referrers = self
+            .entries
+            .get(id)
+            .and_then(|entry| entry.message_id.as_ref())
+            .and_then(|message_id| self.referrers.get(message_id));
+
+        let replies = referrers
+            .into_iter()
+            .flatten()
+            .filter(|candidate| self.parent(candidate).as_ref() == Some(id))
+            .cloned()
+            .collect();
+
+        self.sorted_by_date(replies)
+    }
+
+    /// All emails in the same conversation as the given email (including itself), sorted by date.
+    pub fn thread(&self, id: &entity::Id) -> Vec<entity::Id> {
+        let start = match self.entries.get(id) {
+            Some(entry) => entry,
+            None => return vec![],
+        };
+
+        // Flood fill over the message IDs, starting with the ones mentioned by the given email
+        let mut pending = start.related_message_ids().cloned().collect::<Vec<_>>();
+        let mut seen_message_ids = std::collections::HashSet::new();
+        let mut members = std::collections::HashSet::new();
+
+        members.insert(id.clone());
+
+        while let Some(message_id) = pending.pop() {
+            if !seen_message_ids.insert(message_id.clone()) {
+                continue;
+            }
+
+            for candidate in self.referrers.get(&message_id).into_iter().flatten() {
+                if members.insert(candidate.clone()) {
+                    pending.extend(self.entries[candidate].related_message_ids().cloned());
+                }
+            }
+        }
+
+        self.sorted_by_date(members.into_iter().collect())
+    }
+
+    fn sorted_by_date(&self, mut ids: Vec<entity::Id>) -> Vec<entity::Id> {
+        ids.sort_by(|a, b| {
+            let date_a = self.entries.get(a).and_then(|entry| entry.date);
+            let date_b = self.entries.get(b).and_then(|entry| entry.date);
+            date_a.cmp(&date_b).then_with(|| a.id().cmp(&b.id()))
+        });
+
+        ids
+    }
+}
+
+impl Default for ThreadIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ThreadEntry, ThreadIndex};
+    use crate::core::entity::Id;
+
+    fn id(name: &str) -> Id {
+        Id::Basic(name.to_string())
+    }
+
+    fn entry(message_id: &str, in_reply_to: &[&str], references: &[&str], day: u32) -> ThreadEntry {
+        let strings = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect();
+
+        ThreadEntry {
+            message_id: Some(message_id.to_string()),
+            in_reply_to: strings(in_reply_to),
+            references: strings(references),
+            date: chrono::DateTime::parse_from_rfc3339(&format!("2024-01-{:02}T00:00:00Z", day)).ok(),
+        }
+    }
+
+    /// `a` starts the thread, `b` answers it, `c` answers `b` without saying so in `In-Reply-To`,
+    /// `d` answers `a` although it references `b` as well, `e` answers the missing `x`.
+    fn example() -> ThreadIndex {
+        let mut index = ThreadIndex::new();
+
+        index.add(&id("d"), entry("<d>", &["<a>"], &["<a>", "<b>"], 4));
+        index.add(&id("a"), entry("<a>", &[], &[], 1));
+        index.add(&id("c"), entry("<c>", &[], &["<a>", "<b>"], 3));
+        index.add(&id("b"), entry("<b>", &["<a>"], &["<a>"], 2));
+        index.add(&id("e"), entry("<e>", &["<x>"], &["<c>", "<x>"], 5));
+        index.add(&id("other"), entry("<other>", &[], &[], 1));
+
+        index
+    }
+
+    #[test]
+    fn parents_prefer_in_reply_to() {
+        let index = example();
+
+        assert_eq!(index.parent(&id("a")), None);
+        assert_eq!(index.parent(&id("b")), Some(id("a")));
+        assert_eq!(index.parent(&id("c")), Some(id("b")));
+        assert_eq!(index.parent(&id("d")), Some(id("a")));
+
+        // The message `e` answers is missing, so the closest known ancestor is used
+        assert_eq!(index.parent(&id("e")), Some(id("c")));
+    }
+
+    #[test]
+    fn replies_and_threads() {
+        let index = example();
+
+        assert_eq!(index.replies(&id("a")), vec![id("b"), id("d")]);
+        assert_eq!(index.replies(&id("b")), vec![id("c")]);
+        assert!(index.replies(&id("d")).is_empty());
+
+        let thread = vec![id("a"), id("b"), id("c"), id("d"), id("e")];
+        assert_eq!(index.thread(&id("e")), thread);
+        assert_eq!(index.thread(&id("a")), thread);
+        assert_eq!(index.thread(&id("other")), vec![id("other")]);
+        assert!(index.thread(&id("unknown")).is_empty());
+    }
+
+    #[test]
+    fn threads_survive_missing_messages() {
+        let mut index = ThreadIndex::new();
+
+        // Only the first and the last message of a -> x -> y made it into the vault
+        index.add(&id("a"), entry("<a>", &[], &[], 1));
+        index.add(&id("y"), entry("<y>", &["<x>"], &["<a>", "<x>"], 3));
+
+        assert_eq!(index.thread(&id("a")), vec![id("a"), id("y")]);
+        assert_eq!(index.parent(&id("y")), Some(id("a")));
+    }
+
+    #[test]
+    fn removed_emails_leave_the_thread() {
+        let mut index = example();
+
+        index.remove(&id("b"));
+
+        assert_eq!(index.parent(&id("c")), Some(id("a")));
+        assert_eq!(index.replies(&id("a")), vec![id("c"), id("d")]);
+        assert_eq!(index.thread(&id("a")), vec![id("a"), id("c"), id("d"), id("e")]);
+
+        // Re-adding an email replaces its old headers
+        index.add(&id("e"), entry("<e>", &[], &[], 5));
+        assert_eq!(index.thread(&id("e")), vec![id("e")]);
+
+        for name in ["a", "c", "d", "e", "other"] {
+            index.remove(&id(name));
+        }
+
+        assert!(index.entries.is_empty() && index.by_message_id.is_empty() && index.referrers.is_empty());
+    }
+}
@@ -1,12 +1,78 @@
+use super::volume;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum FileEvent {
-    Created,
-    Modified,
-    Deleted,
+    Created(volume::VolumePath),
+    Modified(volume::VolumePath),
+    Deleted(volume::VolumePath),
 }
 
-#[derive(Debug)]
+impl FileEvent {
+    pub fn path(&self) -> &volume::VolumePath {
+        match self {
+            FileEvent::Created(path) => path,
+            FileEvent::Modified(path) => path,
+            FileEvent::Deleted(path) => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum VaultEvent {
     File(FileEvent),
 }
+
+/// Distributes vault events to everyone who subscribed to them.
+pub struct EventBus {
+    subscribers: std::sync::Mutex<Vec<std::sync::mpsc::Sender<VaultEvent>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            subscribers: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<VaultEvent> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+
+        receiver
+    }
+
+    pub fn publish(&self, event: VaultEvent) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            // Subscribers that dropped their receiver are removed on the fly
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventBus, FileEvent, VaultEvent};
+    use crate::core::vault::volume::{VolumeId, VolumePath};
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let bus = EventBus::new();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+
+        let path = VolumePath::new(VolumeId::hash_string("volume".to_string()), "a.md".into());
+        bus.publish(VaultEvent::File(FileEvent::Created(path)));
+
+        assert!(matches!(kept.try_recv(), Ok(VaultEvent::File(FileEvent::Created(_)))));
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{formats, util::hashing};
+
+use super::{resource, Vault};
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Store the messages as `.eml.gz` instead of `.eml`.
+    pub compress: bool,
+}
+
+/// What happened during an import.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ImportReport {
+    pub imported: Vec<hashing::Sha256>,
+    pub duplicates: usize,
+    /// Sources that could not be read or stored, together with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} imported, {} duplicates skipped, {} failed",
+            self.imported.len(),
+            self.duplicates,
+            self.failed.len()
+        )
+    }
+
+    fn fail<S: ToString>(&mut self, source: &std::path::Path, reason: S) {
+        self.failed
+            .push((source.to_string_lossy().to_string(), reason.to_string()));
+    }
+}
+
+fn is_maildir(path: &std::path::Path) -> bool {
+    path.join("cur").is_dir() && path.join("new").is_dir()
+}
+
+impl Vault {
+    /// Read a source file, decompressing `.eml.gz` files on the fly.
+    fn read_source(path: &std::path::Path) -> Result<Vec<u8>, std::io::Error> {
+        use std::io::Read;
+
+        let mut content = Vec::new();
+        resource::open_file(path)?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    fn import_message(&self, source: &std::path::Path, content: &[u8], options: &ImportOptions, report: &mut ImportReport) {
+        let volume = match self.volumes.email_volume() {
+            Some(volume) => volume,
+            None => return report.fail(source, "No email volume configured"),
+        };
+
+        if formats::email::parse_rfc822(content).is_err() {
+            return report.fail(source, "Not a valid email");
+        }
+
+        match volume.store_email(content, options.compress) {
+            Ok(Some(hash)) => report.imported.push(hash),
+            Ok(None) => report.duplicates += 1,
+            Err(e) => report.fail(source, e),
+        }
+    }
+
+    fn import_file(&self, path: &std::path::Path, options: &ImportOptions, report: &mut ImportReport) {
+        let content = match Self::read_source(path) {
+            Ok(content) => content,
+            Err(e) => return report.fail(path, e),
+        };
+
+        if formats::mbox::looks_like_mbox(&content) {
+            for message in formats::mbox::split_mbox(&content) {
+                self.import_message(path, &message, options, report);
+            }
+        } else {
+            self.import_message(path, &content, options, report);
+        }
+    }
+
+    fn import_maildir(&self, path: &std::path::Path, options: &ImportOptions, report: &mut ImportReport) {
+        // Messages in `tmp` are still being delivered, so only `cur` and `new` are considered
+        for subdirectory in ["cur", "new"] {
+            let entries = match std::fs::read_dir(path.join(subdirectory)) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    report.fail(&path.join(subdirectory), e);
+                    continue;
+                }
+            };
+
+            let mut files = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+
+            files.sort();
+
+            for file in files {
+                match std::fs::read(&file) {
+                    Ok(content) => self.import_message(&file, &content, options, report),
+                    Err(e) => report.fail(&file, e),
+                }
+            }
+        }
+    }
+
+    fn import_directory(&self, path: &std::path::Path, options: &ImportOptions, report: &mut ImportReport) {
+        if is_maildir(path) {
+            return self.import_maildir(path, options, report);
+        }
+
+        let mut entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>(),
+            Err(e) => return report.fail(path, e),
+        };
+
+        entries.sort();
+
+        for entry in entries {
+            if entry.is_dir() {
+                self.import_directory(&entry, options, report);
+            } else if entry.extension().is_some_and(|ext| ext == "eml" || ext == "mbox" || ext == "mbx")
+                || resource::transparently_compressed_path(&entry).is_some()
+            {
+                self.import_file(&entry, options, report);
+            }
+        }
+    }
+
+    /// Import emails from a single `.eml` file, an mbox archive, a Maildir, or a directory containing any of these.
+    /// Every message is stored in the email volume under its hash, messages that are already present are skipped.
+    pub fn import_emails(&self, path: &std::path::Path, options: &ImportOptions) -> ImportReport {
+        let mut report = ImportReport::default();
+
+        if path.is_dir() {
+            self.import_directory(path, options, &mut report);
+        } else {
+            self.import_file(path, options, &mut report);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImportOptions;
+    use crate::core::{
+        config::{ConfigBuilder, VolumeConfig, VolumeKind},
+        vault::{volume::flags::Flags, Vault},
+    };
+    use crate::util::testing::TempDir;
+
+    fn message(subject: &str) -> String {
+        format!("From: someone@example.com\nSubject: {}\n\nHello\n", subject)
+    }
+
+    fn open_vault(dir: &TempDir) -> Vault {
+        std::fs::create_dir_all(dir.path().join("mail")).unwrap();
+
+        ConfigBuilder::new()
+            .cache_path(dir.path().join("cache"))
+            .volume(VolumeConfig::new(VolumeKind::Email, dir.path().join("mail"), Flags::new()))
+            .open_vault()
+            .unwrap()
+    }
+
+    #[test]
+    fn duplicates_are_skipped() {
+        let dir = TempDir::new("import");
+        let vault = open_vault(&dir);
+
+        let mbox = format!(
+            "From a\n{}\nFrom b\n{}\nFrom c\n{}",
+            message("One"),
+            message("One"),
+            message("Two")
+        );
+        let archive = dir.write("import/archive.mbox", &mbox);
+
+        let report = vault.import_emails(&archive, &ImportOptions::default());
+        assert_eq!((report.imported.len(), report.duplicates, report.failed.len()), (2, 1, 0));
+
+        // A Maildir with a message that is already known and one that is new
+        dir.write("import/maildir/cur/1", &message("Two"));
+        dir.write("import/maildir/new/2", &message("Three"));
+        std::fs::create_dir_all(dir.path().join("import/maildir/tmp")).unwrap();
+
+        let report = vault.import_emails(&dir.path().join("import/maildir"), &ImportOptions::default());
+        assert_eq!((report.imported.len(), report.duplicates, report.failed.len()), (1, 1, 0));
+
+        // Importing everything again only finds duplicates
+        let report = vault.import_emails(&dir.path().join("import"), &ImportOptions::default());
+        assert_eq!((report.imported.len(), report.duplicates, report.failed.len()), (0, 5, 0));
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+
+use crate::core::entity;
+
+use super::{caching, Vault};
+
+impl Vault {
+    /// Write the caches and the search index to disk.
+    pub fn save_cache(&self) {
+        if let Ok(mut cache) = self.cache.write() {
+            cache.save().ok();
+        }
+    }
+
+    /// Throw away all cached information and recompute it by scanning every volume.
+    pub fn rebuild_cache(&self) -> Result<(), std::io::Error> {
+        match self.cache.write() {
+            Ok(mut cache) => cache.clear()?,
+            Err(_) => return Err(std::io::Error::other("Cache is poisoned")),
+        }
+
+        for id in self.list_entities() {
+            self.title_of_entity(&id);
+        }
+
+        self.update_search_index();
+        self.ensure_backlinks();
+        self.ensure_tags();
+        self.ensure_threads();
+
+        match self.cache.write() {
+            Ok(mut cache) => cache.save(),
+            Err(_) => Err(std::io::Error::other("Cache is poisoned")),
+        }
+    }
+
+    /// Remove cache entries of files that no longer exist. Returns the number of removed entries.
+    pub fn collect_garbage(&self) -> usize {
+        let mut cache = match self.cache.write() {
+            Ok(cache) => cache,
+            Err(_) => return 0,
+        };
+
+        let mut paths = HashSet::new();
+        let mut ids = HashSet::new();
+
+        // Entities in hidden volumes are cached as well, so they have to be kept
+        for resource in self.volumes.list_resources() {
+            ids.insert(entity::Id::for_resource(&resource, self, &mut cache));
+            paths.insert(resource.volume_path().clone());
+        }
+
+        cache.collect_garbage(&paths, &ids)
+    }
+
+    pub fn cache_stats(&self) -> Option<caching::CacheStats> {
+        self.cache.write().ok().map(|mut cache| cache.stats())
+    }
+}
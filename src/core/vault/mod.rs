@@ -7,12 +7,18 @@ use crate::{core::vault, semantic};
 
 pub mod caching;
 pub mod event;
+pub mod import;
 pub mod resource;
+pub mod tasks;
 pub mod volume;
 
+mod maintenance;
+mod search;
+
 pub struct Vault {
     volumes: vault::volume::Volumes,
     cache: std::sync::RwLock<caching::GlobalCache>,
+    events: event::EventBus,
 }
 
 pub type VaultOpenResult = Result<Vault, ()>;
@@ -29,37 +35,103 @@ impl Vault {
             })?;
         }
 
-        let volumes = vec![
-            vault::volume::volumes::directory::DirectoryVolume::new(
-                config.vault_path.unwrap(),
-                vault::volume::flags::Flags::new().with_zettels(),
-            )
-            .into(),
-        ];
+        let mut volumes: Vec<vault::volume::VolumeEnum> = Vec::new();
+
+        for volume in config.volumes {
+            match volume.kind {
+                config::VolumeKind::Directory => {
+                    if !volume.path.is_dir() {
+                        eprintln!("Volume directory {:?} does not exist", volume.path);
+                        return Err(());
+                    }
+
+                    volumes.push(
+                        vault::volume::volumes::directory::DirectoryVolume::new(volume.path, volume.flags).into(),
+                    );
+                }
+                config::VolumeKind::Email => {
+                    // The email volume is filled by the importer, so it may not exist yet
+                    std::fs::create_dir_all(&volume.path).map_err(|_| {
+                        eprintln!("Unable to create email directory at {:?}", volume.path);
+                    })?;
+
+                    volumes.push(vault::volume::volumes::email::EmailVolume::new(volume.path, volume.flags).into());
+                }
+            }
+        }
+
+        if volumes.is_empty() {
+            eprintln!("No volumes configured");
+            return Err(());
+        }
 
         let vault = Self {
             volumes: vault::volume::Volumes::new(volumes),
             cache: std::sync::RwLock::new(caching::GlobalCache::new(cache_path)),
+            events: event::EventBus::new(),
         };
 
         Ok(vault)
     }
 
     pub fn list_entities(&self) -> Vec<entity::Id> {
+        self.list_resources_with_ids()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    fn list_resources_with_ids(&self) -> Vec<(entity::Id, vault::resource::Resource)> {
         match self.cache.write() {
             Ok(mut cache) => self
                 .volumes
-                .list_resources()
-                .map(move |resource| entity::Id::for_resource(&resource, self, &mut cache))
+                .list_visible_resources()
+                .map(move |resource| (entity::Id::for_resource(&resource, self, &mut cache), resource))
                 .collect::<Vec<_>>(),
             Err(_) => vec![],
         }
     }
 
     fn find_resource_for_id(&self, id: &entity::Id) -> Option<vault::resource::Resource> {
-        match self.cache.write() {
+        let resource = match self.cache.write() {
             Ok(mut cache) => self.volumes.find_resource_for_id(id, self, &mut cache),
             Err(_) => None,
+        };
+
+        // Links may also use one of the aliases of a note
+        resource.or_else(|| self.resource_by_alias(id))
+    }
+
+    fn resource_by_alias(&self, id: &entity::Id) -> Option<vault::resource::Resource> {
+        let entity::Id::Basic(name) = id else {
+            return None;
+        };
+
+        let target = self.entities_by_alias(name).into_iter().next()?;
+
+        match self.cache.write() {
+            Ok(mut cache) => self.volumes.find_resource_for_id(&target, self, &mut cache),
+            Err(_) => None,
+        }
+    }
+
+    /// Everything a link to `[[name]]` could point to, the one it resolves to first. More than
+    /// one candidate means that the link is ambiguous, none that it is broken.
+    pub fn link_candidates(&self, name: &str) -> Vec<vault::resource::Resource> {
+        let resources = self.volumes.resources_by_short_name(name);
+
+        if !resources.is_empty() {
+            return resources;
+        }
+
+        let targets = self.entities_by_alias(name);
+
+        match self.cache.write() {
+            Ok(mut cache) => targets
+                .iter()
+                .filter_map(|target| self.volumes.find_resource_for_id(target, self, &mut cache))
+                .collect(),
+            Err(_) => vec![],
         }
     }
 
@@ -72,11 +144,25 @@ impl Vault {
     }
 
     pub fn load_entity(&self, id: &entity::Id) -> Option<entity::Entity> {
+        if let entity::Id::Attachment(parent, index) = id {
+            return self.load_attachment(parent, *index).map(entity::Entity::File);
+        }
+
         let resource = self.find_resource_for_id(id)?;
 
         entity::Entity::from_resource(resource, self).ok()
     }
 
+    /// Attachments have no resource of their own, they are extracted from their parent entity.
+    pub fn load_attachment(&self, parent: &entity::Id, index: usize) -> Option<vault::resource::file::FileContent> {
+        match self.load_entity(parent)? {
+            entity::Entity::Email(email) => email
+                .attachment(index)
+                .map(|attachment| attachment.to_file_content(index)),
+            _ => None,
+        }
+    }
+
     pub fn load_zettel(&self, id: &entity::Id) -> Option<zettel::Zettel> {
         if let Some(entity::Entity::Zettel(zettel)) = self.load_entity(id) {
             Some(zettel)
@@ -89,6 +175,369 @@ impl Vault {
         self.load_zettel(id).map(|zettel| zettel.header)
     }
 
+    /// Load the unprocessed source of a zettel (including its frontmatter), e.g. for editing.
+    pub fn load_zettel_source(&self, id: &entity::Id) -> Option<String> {
+        let resource = self.find_resource_for_id(id)?;
+
+        match resource.resource_type() {
+            Some(vault::resource::Type::Zettel(_)) => resource.read_to_string(self).ok(),
+            _ => None,
+        }
+    }
+
+    /// Replace the source of a zettel and drop all cached information derived from it.
+    pub fn save_zettel_source(&self, id: &entity::Id, content: &str) -> Result<(), std::io::Error> {
+        let resource = self.find_resource_for_id(id).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Entity not found")
+        })?;
+
+        match resource.resource_type() {
+            Some(vault::resource::Type::Zettel(_)) => {}
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Entity is not a zettel",
+                ))
+            }
+        }
+
+        resource.write_bytes(self, content.as_bytes())?;
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.invalidate_path(resource.volume_path());
+            cache.invalidate_id(id);
+        }
+
+        self.refresh_indices_of(id);
+
+        Ok(())
+    }
+
+    /// Resolve an ID as it appears in a reference (e.g. `nijakow.png`) to the ID the entity is listed under.
+    pub fn canonical_id(&self, id: &entity::Id) -> Option<entity::Id> {
+        if let entity::Id::Attachment(parent, index) = id {
+            self.load_attachment(parent, *index)?;
+            return self.canonical_id(parent).map(|parent| parent.attachment(*index));
+        }
+
+        let resource = self.find_resource_for_id(id)?;
+
+        match self.cache.write() {
+            Ok(mut cache) => Some(entity::Id::for_resource(&resource, self, &mut cache)),
+            Err(_) => None,
+        }
+    }
+
+    /// Collect the canonical IDs of all entities referenced by the given entity,
+    /// together with the text of the paragraph containing the reference.
+    pub fn outgoing_links(&self, id: &entity::Id) -> Vec<(entity::Id, Option<String>)> {
+        use entity::link::reference::Reference;
+        use semantic::Scannable;
+
+        let zettel = match self.load_zettel(id) {
+            Some(zettel) => zettel,
+            None => return vec![],
+        };
+
+        let mut references = Vec::new();
+
+        zettel.header.iterate_info_items(&mut |item| {
+            if let semantic::InfoItem::Link(reference) = item {
+                references.push((reference, None));
+            }
+        });
+
+        if let Some(document) = zettel.body_as_document() {
+            for block in document.blocks() {
+                block.visit_leaves(&mut |leaf| {
+                    let context = Some(leaf.to_plain_text().trim().to_string()).filter(|c| !c.is_empty());
+
+                    leaf.iterate_info_items(&mut |item| {
+                        if let semantic::InfoItem::Link(reference) = item {
+                            references.push((reference, context.clone()));
+                        }
+                    });
+                });
+            }
+        }
+
+        references
+            .into_iter()
+            .filter_map(|(reference, context)| match reference {
+                Reference::Entity(target) => self.canonical_id(&target).map(|target| (target, context)),
+                Reference::Url(_) => None,
+            })
+            .filter(|(target, _)| target != id)
+            .collect()
+    }
+
+    fn ensure_backlinks(&self) {
+        let populated = match self.cache.read() {
+            Ok(cache) => cache.backlinks().is_some(),
+            Err(_) => return,
+        };
+
+        if populated {
+            return;
+        }
+
+        let mut index = caching::backlinks::BacklinkIndex::new();
+
+        for source in self.list_entities() {
+            for (target, context) in self.outgoing_links(&source) {
+                index.add(&source, &target, context);
+            }
+        }
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.set_backlinks(index);
+        }
+    }
+
+    /// The tags of a single entity, in the order they appear.
+    pub fn tags_of_entity(&self, id: &entity::Id) -> Vec<String> {
+        use semantic::Scannable;
+
+        let mut tags = Vec::new();
+
+        if let Some(entity) = self.load_entity(id) {
+            entity.iterate_info_items(&mut |item| {
+                if let semantic::InfoItem::Tag(tag) = item {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            });
+        }
+
+        tags
+    }
+
+    fn ensure_tags(&self) {
+        let populated = match self.cache.read() {
+            Ok(cache) => cache.tags().is_some(),
+            Err(_) => return,
+        };
+
+        if populated {
+            return;
+        }
+
+        let mut index = caching::tags::TagIndex::new();
+
+        for source in self.list_entities() {
+            for tag in self.tags_of_entity(&source) {
+                index.add(&source, &tag);
+            }
+        }
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.set_tags(index);
+        }
+    }
+
+    /// The aliases of a single entity, only zettels have some.
+    fn aliases_of_entity(&self, id: &entity::Id) -> Vec<String> {
+        self.load_zettel_header(id)
+            .map(|header| header.aliases())
+            .unwrap_or_default()
+    }
+
+    fn ensure_aliases(&self) {
+        let populated = match self.cache.read() {
+            Ok(cache) => cache.aliases().is_some(),
+            Err(_) => return,
+        };
+
+        if populated {
+            return;
+        }
+
+        let mut index = caching::aliases::AliasIndex::new();
+
+        // The entities are loaded from their resources directly, looking them up by ID could need the aliases again
+        for (source, resource) in self.list_resources_with_ids() {
+            if let Ok(entity::Entity::Zettel(zettel)) = entity::Entity::from_resource(resource, self) {
+                for alias in zettel.header.aliases() {
+                    index.add(&source, &alias);
+                }
+            }
+        }
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.set_aliases(index);
+        }
+    }
+
+    /// The entities using the given alias.
+    pub fn entities_by_alias(&self, alias: &str) -> Vec<entity::Id> {
+        self.ensure_aliases();
+
+        match self.cache.read() {
+            Ok(cache) => cache
+                .aliases()
+                .map(|index| index.entities(alias))
+                .unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn thread_entry_of_entity(&self, id: &entity::Id) -> Option<caching::threads::ThreadEntry> {
+        match self.load_entity(id)? {
+            entity::Entity::Email(email) => Some(email.thread_entry()),
+            _ => None,
+        }
+    }
+
+    fn ensure_threads(&self) {
+        let populated = match self.cache.read() {
+            Ok(cache) => cache.threads().is_some(),
+            Err(_) => return,
+        };
+
+        if populated {
+            return;
+        }
+
+        let mut index = caching::threads::ThreadIndex::new();
+
+        let emails = self
+            .list_resources_with_ids()
+            .into_iter()
+            .filter(|(_, resource)| {
+                matches!(
+                    resource.resource_type(),
+                    Some(vault::resource::Type::Other(vault::resource::types::OtherType::Email))
+                )
+            });
+
+        for (id, _) in emails {
+            if let Some(entry) = self.thread_entry_of_entity(&id) {
+                index.add(&id, entry);
+            }
+        }
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.set_threads(index);
+        }
+    }
+
+    /// Re-scan the references and tags of a single entity, e.g. after it was modified.
+    fn refresh_indices_of(&self, source: &entity::Id) {
+        let links = self.outgoing_links(source);
+        let tags = self.tags_of_entity(source);
+        let aliases = self.aliases_of_entity(source);
+        let thread_entry = self.thread_entry_of_entity(source);
+
+        if let Ok(mut cache) = self.cache.write() {
+            if let Some(index) = cache.backlinks_mut() {
+                index.remove_source(source);
+
+                for (target, context) in links {
+                    index.add(source, &target, context);
+                }
+            }
+
+            if let Some(index) = cache.tags_mut() {
+                index.remove_source(source);
+
+                for tag in tags {
+                    index.add(source, &tag);
+                }
+            }
+
+            if let Some(index) = cache.aliases_mut() {
+                index.remove_source(source);
+
+                for alias in aliases {
+                    index.add(source, &alias);
+                }
+            }
+
+            if let Some(index) = cache.threads_mut() {
+                match thread_entry {
+                    Some(entry) => index.add(source, entry),
+                    None => index.remove(source),
+                }
+            }
+        }
+
+        self.refresh_search_entry(source);
+    }
+
+    /// List all entities referring to the given entity.
+    pub fn backlinks(&self, id: &entity::Id) -> Vec<caching::backlinks::Backlink> {
+        self.ensure_backlinks();
+
+        match self.cache.read() {
+            Ok(cache) => cache
+                .backlinks()
+                .map(|index| index.backlinks(id).to_vec())
+                .unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// List all tags in the vault together with the number of entities carrying them.
+    pub fn tags(&self) -> Vec<(String, usize)> {
+        self.ensure_tags();
+
+        match self.cache.read() {
+            Ok(cache) => cache.tags().map(|index| index.tags()).unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// List the direct children of a nested tag.
+    pub fn subtags(&self, tag: &str) -> Vec<(String, usize)> {
+        self.ensure_tags();
+
+        match self.cache.read() {
+            Ok(cache) => cache.tags().map(|index| index.subtags(tag)).unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// List all entities carrying the given tag or one of its nested tags.
+    pub fn entities_with_tag(&self, tag: &str) -> Vec<entity::Id> {
+        self.ensure_tags();
+
+        match self.cache.read() {
+            Ok(cache) => cache.tags().map(|index| index.entities(tag)).unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// The email the given email replies to.
+    pub fn email_parent(&self, id: &entity::Id) -> Option<entity::Id> {
+        self.ensure_threads();
+
+        match self.cache.read() {
+            Ok(cache) => cache.threads().and_then(|index| index.parent(id)),
+            Err(_) => None,
+        }
+    }
+
+    /// The emails replying to the given email, oldest first.
+    pub fn email_replies(&self, id: &entity::Id) -> Vec<entity::Id> {
+        self.ensure_threads();
+
+        match self.cache.read() {
+            Ok(cache) => cache.threads().map(|index| index.replies(id)).unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// All emails of the conversation the given email is part of, oldest first.
+    pub fn email_thread(&self, id: &entity::Id) -> Vec<entity::Id> {
+        self.ensure_threads();
+
+        match self.cache.read() {
+            Ok(cache) => cache.threads().map(|index| index.thread(id)).unwrap_or_default(),
+            Err(_) => vec![],
+        }
+    }
+
     pub fn title_of_entity(&self, id: &entity::Id) -> Option<String> {
         let perhaps_title = match self.cache.write() {
             Ok(mut cache) => cache.get_title(id),
@@ -103,10 +552,12 @@ impl Vault {
 
         let title = match entity {
             entity::Entity::File(file) => file.metadata().title(),
+            entity::Entity::Email(email) => email.title().or_else(|| Some(id.id().to_string())),
             entity::Entity::Zettel(zettel) => zettel
                 .header()
                 .title
                 .clone()
+                .or_else(|| zettel.body_as_document().and_then(|document| document.title()))
                 .or_else(|| Some(id.id().to_string())),
         };
 
@@ -119,13 +570,115 @@ impl Vault {
         title
     }
 
-    pub fn tick(&self) {
-        self.volumes.tick();
+    /// Get notified about everything that changes in the vault.
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<event::VaultEvent> {
+        self.events.subscribe()
+    }
 
-        if let Ok(mut cache) = self.cache.write() {
-            cache.save().ok();
+    /// Whether the path belongs to a volume that is neither listed nor indexed.
+    fn is_in_hidden_volume(&self, path: &volume::VolumePath) -> bool {
+        use volume::Volume;
+
+        self.volumes
+            .volume_by_id(path.volume())
+            .is_some_and(|volume| volume.flags().is_hidden)
+    }
+
+    /// The resource at the given path, its volume decides whether Markdown files are zettels.
+    fn resource_at(&self, path: &volume::VolumePath) -> vault::resource::Resource {
+        use volume::Volume;
+
+        let has_zettels = self
+            .volumes
+            .volume_by_id(path.volume())
+            .is_some_and(|volume| volume.flags().has_zettels);
+
+        vault::resource::Resource::from_path(path.clone()).with_zettels(has_zettels)
+    }
+
+    /// Update the caches and indices after a file was created, modified or deleted outside of Athena.
+    fn handle_file_event(&self, file_event: event::FileEvent) {
+        let resource = self.resource_at(file_event.path());
+        let is_hidden = self.is_in_hidden_volume(resource.volume_path());
+        let is_deleted = matches!(file_event, event::FileEvent::Deleted(_));
+        let is_modified = matches!(file_event, event::FileEvent::Modified(_));
+
+        let old_id = match self.cache.write() {
+            Ok(mut cache) => {
+                // The ID of a new file can't be stale, everything else has to be looked up before invalidating
+                let old_id = match file_event {
+                    event::FileEvent::Created(_) => None,
+                    _ => entity::Id::for_resource_if_known(&resource, self, &mut cache),
+                };
+
+                cache.invalidate_path(resource.volume_path());
+
+                if let Some(old_id) = &old_id {
+                    cache.invalidate_id(old_id);
+                    cache.search_index_mut().remove(old_id);
+
+                    if let Some(index) = cache.tags_mut() {
+                        index.remove_source(old_id);
+                    }
+
+                    if let Some(index) = cache.aliases_mut() {
+                        index.remove_source(old_id);
+                    }
+
+                    if let Some(index) = cache.threads_mut() {
+                        index.remove(old_id);
+                    }
+                }
+
+                // Links that could not be resolved before might point to the new file now (and vice versa)
+                if !is_modified {
+                    cache.reset_backlinks();
+                }
+
+                old_id
+            }
+            Err(_) => return,
+        };
+
+        // Hidden volumes are not indexed
+        if !is_deleted && !is_hidden {
+            let new_id = match self.cache.write() {
+                Ok(mut cache) => {
+                    let new_id = entity::Id::for_resource(&resource, self, &mut cache);
+                    cache.invalidate_id(&new_id);
+                    new_id
+                }
+                Err(_) => return,
+            };
+
+            // Entities referring to the old content now refer to nothing, so they have to be rescanned
+            if let Some(old_id) = old_id.filter(|old_id| *old_id != new_id) {
+                for backlink in self.backlinks(&old_id) {
+                    self.refresh_indices_of(&backlink.source);
+                }
+            }
+
+            self.refresh_indices_of(&new_id);
+        }
+
+        self.events.publish(event::VaultEvent::File(file_event));
+    }
+
+    fn handle_file_events(&self, file_events: Vec<event::FileEvent>) {
+        for file_event in file_events {
+            self.handle_file_event(file_event);
         }
     }
+
+    /// Process the changes the volumes have been notified about. This is cheap and can be called often.
+    pub fn process_events(&self) {
+        self.handle_file_events(self.volumes.take_events());
+    }
+
+    pub fn tick(&self) {
+        self.handle_file_events(self.volumes.tick());
+        self.save_cache();
+    }
 }
 
 impl resource::ResourceInterface for Vault {
@@ -142,6 +695,21 @@ impl resource::ResourceInterface for Vault {
 
         volume.open_path(path)
     }
+
+    fn write_contents(
+        &self,
+        path: &volume::VolumePath,
+        content: &[u8],
+    ) -> Result<(), std::io::Error> {
+        use volume::Volume;
+
+        let volume = self
+            .volumes
+            .volume_by_id(path.volume())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Volume not found"))?;
+
+        volume.write_path(path, content)
+    }
 }
 
 impl semantic::Scannable for Vault {
@@ -153,3 +721,57 @@ impl semantic::Scannable for Vault {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{event, Vault};
+    use crate::core::{config, entity::Id};
+    use crate::util::testing::TempDir;
+
+    /// Let the vault pick up the changes on disk, until it reports one.
+    fn next_event(vault: &Vault, events: &std::sync::mpsc::Receiver<event::VaultEvent>) -> event::FileEvent {
+        for _ in 0..100 {
+            vault.process_events();
+            vault.tick();
+
+            if let Ok(event::VaultEvent::File(file_event)) = events.try_recv() {
+                return file_event;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+
+        panic!("No file event");
+    }
+
+    #[test]
+    fn caches_follow_changes_on_disk() {
+        let dir = TempDir::new("file-events");
+        dir.write("vault/a.md", "# A\n");
+
+        let vault = config::ConfigBuilder::new()
+            .cache_path(dir.path().join("cache"))
+            .vault_path(dir.path().join("vault"))
+            .open_vault()
+            .unwrap();
+
+        let events = vault.subscribe();
+        let id = Id::Basic("New Note".to_string());
+
+        assert!(vault.link_candidates("sub/new note").is_empty());
+
+        dir.write("vault/sub/New Note.md", "# Fresh\n");
+        assert!(matches!(next_event(&vault, &events), event::FileEvent::Created(_)));
+        assert_eq!(vault.link_candidates("sub/new note").len(), 1);
+        assert_eq!(vault.title_of_entity(&id).as_deref(), Some("Fresh"));
+
+        dir.write("vault/sub/New Note.md", "# Changed title\n");
+        assert!(matches!(next_event(&vault, &events), event::FileEvent::Modified(_)));
+        assert_eq!(vault.title_of_entity(&id).as_deref(), Some("Changed title"));
+
+        std::fs::remove_file(dir.path().join("vault/sub/New Note.md")).unwrap();
+        assert!(matches!(next_event(&vault, &events), event::FileEvent::Deleted(_)));
+        assert!(vault.link_candidates("sub/new note").is_empty());
+        assert!(vault.load_zettel(&id).is_none());
+    }
+}
@@ -5,7 +5,8 @@ pub mod file;
 pub mod types;
 
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(tag = "category", content = "format", rename_all = "snake_case")]
 pub enum Type {
     Zettel(types::ZettelType),
     Document(types::DocumentType),
@@ -115,6 +116,10 @@ impl Type {
 
 pub trait ResourceInterface {
     fn open_for_reading(&self, path: &volume::VolumePath) -> Result<Box<dyn std::io::Read>, std::io::Error>;
+
+    /// Replace the content at the given path. Volumes are expected to do this atomically,
+    /// so that readers never observe a half-written file.
+    fn write_contents(&self, path: &volume::VolumePath, content: &[u8]) -> Result<(), std::io::Error>;
 }
 
 
@@ -122,20 +127,67 @@ pub struct Metadata {
     pub resource_type: Option<Type>,
 }
 
-#[derive(Debug)]
+/// Emails may be stored gzip-compressed (`.eml.gz`). Volumes decompress them transparently,
+/// so everywhere else they behave like the `.eml` file returned here.
+pub fn transparently_compressed_path(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    if path.extension()? != "gz" {
+        return None;
+    }
+
+    let inner = path.with_extension("");
+
+    if inner.extension()? == "eml" {
+        Some(inner)
+    } else {
+        None
+    }
+}
+
+/// Open a file, decompressing it if it is transparently compressed.
+pub fn open_file(path: &std::path::Path) -> Result<Box<dyn std::io::Read>, std::io::Error> {
+    let file = std::fs::File::open(path)?;
+
+    if transparently_compressed_path(path).is_some() {
+        Ok(Box::new(flate2::read::GzDecoder::new(std::io::BufReader::new(file))))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Resource {
     path: volume::VolumePath,
+    /// Whether Markdown files are zettels, otherwise they are plain files.
+    zettels: bool,
 }
 
 impl Resource {
     pub fn from_path(path: volume::VolumePath) -> Self {
-        Self { path }
+        Self { path, zettels: true }
+    }
+
+    pub fn with_zettels(mut self, zettels: bool) -> Self {
+        self.zettels = zettels;
+        self
+    }
+
+    /// The path as seen by the rest of Athena, i.e. without the compression suffix.
+    fn logical_path(&self) -> std::path::PathBuf {
+        let path = self.path.path();
+        transparently_compressed_path(path).unwrap_or_else(|| path.to_path_buf())
     }
 
     pub fn metadata(&self) -> Metadata {
-        let extension = self.path.path().extension().and_then(|e| e.to_str());
+        let path = self.logical_path();
+        let extension = path.extension().and_then(|e| e.to_str());
         let resource_type = extension.and_then(|e| Type::from_extension(e));
 
+        // Volumes without zettels serve their notes like any other text file
+        let resource_type = match resource_type {
+            Some(Type::Zettel(_)) if !self.zettels => Some(Type::Document(types::DocumentType::PlainText)),
+            resource_type => resource_type,
+        };
+
         Metadata { resource_type }
     }
 
@@ -144,8 +196,7 @@ impl Resource {
     }
 
     pub fn file_name_without_extension(&self) -> Option<String> {
-        self.path
-            .path()
+        self.logical_path()
             .file_stem()
             .and_then(|s| s.to_str())
             .map(|s| s.to_string())
@@ -200,11 +251,14 @@ impl Resource {
             })
     }
 
+    pub fn write_bytes(&self, resource_interface: &dyn ResourceInterface, content: &[u8]) -> Result<(), std::io::Error> {
+        resource_interface.write_contents(&self.path, content)
+    }
+
     pub fn read_content(&self, resource_interface: &dyn ResourceInterface) -> Result<file::FileContent, std::io::Error> {
         // TODO, FIXME, XXX: Actually ask the volume! Don't ignore the volume ID!
         let title = self
-            .path
-            .path()
+            .logical_path()
             .file_stem()
             .and_then(|s| s.to_str())
             .map(|s| s.to_string());
@@ -1,19 +1,22 @@
 use super::Type;
 
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ZettelType {
     Athena,
     Obsidian,
 }
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DocumentType {
     PlainText,
     Pdf,
 }
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ImageType {
     Png,
     Jpg,
@@ -23,21 +26,24 @@ pub enum ImageType {
     Bmp,
 }
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum AudioType {
     Mp3,
     Ogg,
     Wav,
 }
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum VideoType {
     Mp4,
     Webm,
     Ogg,
 }
 
-#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum OtherType {
     Email,
 }
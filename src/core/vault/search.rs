@@ -0,0 +1,198 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    core::entity,
+    search,
+    util::hashing,
+};
+
+use super::{resource, Vault};
+
+impl Vault {
+    /// Something that changes whenever the content of the resource changes.
+    ///
+    /// Files that usually stay the same reuse the hash the cache remembers for their path, notes are hashed
+    /// again since they may have been edited while Athena wasn't running.
+    fn search_fingerprint(&self, id: &entity::Id, resource: &resource::Resource) -> Option<hashing::Sha256> {
+        match id {
+            entity::Id::Sha256(hash) | entity::Id::Email(hash) => Some(hash.clone()),
+            entity::Id::Basic(_) | entity::Id::Attachment(..) => {
+                let cached = match self.cache.write() {
+                    Ok(mut cache) => resource.content_hash(self, &mut cache),
+                    Err(_) => None,
+                };
+
+                cached.or_else(|| {
+                    resource
+                        .read_to_bytes(self)
+                        .ok()
+                        .map(|bytes| hashing::Sha256::hash_bytes(&bytes))
+                })
+            }
+        }
+    }
+
+    fn search_document_for(
+        &self,
+        id: &entity::Id,
+        resource: &resource::Resource,
+        fingerprint: hashing::Sha256,
+    ) -> search::IndexedDocument {
+        use search::{Field, FieldKind};
+
+        let title = self
+            .title_of_entity(id)
+            .unwrap_or_else(|| id.as_readable_string());
+
+        let file_name = resource
+            .volume_path()
+            .path()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut fields = vec![
+            Field::new(FieldKind::Title, title.clone()),
+            Field::new(FieldKind::FileName, file_name),
+        ];
+
+        let mut aliases = vec![];
+
+        // Only zettels and emails are parsed, everything else is just indexed by its name
+        if let Some(resource::Type::Other(resource::types::OtherType::Email)) = resource.resource_type() {
+            if let Ok(entity::Entity::Email(email)) = entity::Entity::from_resource(resource.clone(), self) {
+                for address in email.headers.from.iter().chain(&email.headers.to).chain(&email.headers.cc) {
+                    fields.push(Field::new(FieldKind::Frontmatter, address.display()));
+                }
+
+                if let Some(text) = email.text() {
+                    fields.push(Field::new(FieldKind::Text, text));
+                }
+            }
+        }
+
+        if let Some(resource::Type::Zettel(_)) = resource.resource_type() {
+            if let Ok(entity::Entity::Zettel(zettel)) = entity::Entity::from_resource(resource.clone(), self) {
+                aliases = zettel.header.aliases();
+
+                for alias in &aliases {
+                    fields.push(Field::new(FieldKind::Alias, alias.clone()));
+                }
+
+                for (key, line) in &zettel.header.lines {
+                    if key == "aliases" || key == "alias" {
+                        continue;
+                    }
+
+                    for element in line.elements() {
+                        if let Some(text) = element.to_plain_text() {
+                            fields.push(Field::new(FieldKind::Frontmatter, text));
+                        }
+                    }
+                }
+
+                if let Some(document) = zettel.body_as_document() {
+                    for block in document.blocks() {
+                        block.visit_leaves(&mut |leaf| {
+                            let kind = match leaf {
+                                entity::zettel::document::block::Block::Heading(_) => FieldKind::Heading,
+                                _ => FieldKind::Text,
+                            };
+
+                            let text = leaf.to_plain_text();
+
+                            if !text.trim().is_empty() {
+                                fields.push(Field::new(kind, text));
+                            }
+                        });
+                    }
+                }
+            }
+        }
+
+        search::IndexedDocument {
+            fingerprint,
+            title,
+            aliases,
+            fields,
+        }
+    }
+
+    /// Bring the search index up to date. Only entities whose content changed are re-indexed.
+    pub fn update_search_index(&self) {
+        let resources = self.list_resources_with_ids();
+
+        let known: HashMap<entity::Id, hashing::Sha256> = match self.cache.read() {
+            Ok(cache) => {
+                let index = cache.search_index();
+                index
+                    .ids()
+                    .filter_map(|id| Some((id.clone(), index.fingerprint(id)?.clone())))
+                    .collect()
+            }
+            Err(_) => return,
+        };
+
+        let mut updates = Vec::new();
+
+        for (id, resource) in &resources {
+            let fingerprint = match self.search_fingerprint(id, resource) {
+                Some(fingerprint) => fingerprint,
+                None => continue,
+            };
+
+            if known.get(id) != Some(&fingerprint) {
+                updates.push((id.clone(), self.search_document_for(id, resource, fingerprint)));
+            }
+        }
+
+        let present = resources.into_iter().map(|(id, _)| id).collect::<HashSet<_>>();
+
+        if let Ok(mut cache) = self.cache.write() {
+            let index = cache.search_index_mut();
+
+            for id in known.keys().filter(|id| !present.contains(id)) {
+                index.remove(id);
+            }
+
+            for (id, document) in updates {
+                index.insert(id, document);
+            }
+
+            index.mark_synchronized();
+        }
+    }
+
+    /// Re-index a single entity, e.g. after it was modified.
+    pub(super) fn refresh_search_entry(&self, id: &entity::Id) {
+        let document = self.find_resource_for_id(id).and_then(|resource| {
+            let fingerprint = self.search_fingerprint(id, &resource)?;
+            Some(self.search_document_for(id, &resource, fingerprint))
+        });
+
+        if let Ok(mut cache) = self.cache.write() {
+            match document {
+                Some(document) => cache.search_index_mut().insert(id.clone(), document),
+                None => cache.search_index_mut().remove(id),
+            }
+        }
+    }
+
+    pub fn search(&self, query: &str) -> Vec<search::SearchHit> {
+        let synchronized = match self.cache.read() {
+            Ok(cache) => cache.search_index().is_synchronized(),
+            Err(_) => return vec![],
+        };
+
+        if !synchronized {
+            self.update_search_index();
+        }
+
+        let query = search::Query::parse(query);
+
+        match self.cache.read() {
+            Ok(cache) => cache.search_index().search(&query),
+            Err(_) => vec![],
+        }
+    }
+}
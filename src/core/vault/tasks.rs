@@ -0,0 +1,303 @@
+use crate::{
+    core::entity::{self, zettel::document::block::bullet_point::TaskInfo},
+    semantic,
+    util::hashing,
+};
+
+use super::{caching::tags::normalize_tag, Vault};
+
+/// A task somewhere in the vault, together with the entity it is written in.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Task {
+    pub entity: entity::Id,
+    pub info: TaskInfo,
+    pub text: String,
+    /// The tags of the task itself, followed by those of its entity.
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    /// Only pending and in-progress tasks.
+    pub open: bool,
+    /// Only open tasks whose due date has passed.
+    pub overdue: bool,
+    /// Only tasks carrying this tag or one of its nested tags.
+    pub tag: Option<String>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task, today: chrono::NaiveDate) -> bool {
+        if self.open && !task.info.is_open() {
+            return false;
+        }
+
+        if self.overdue && !task.info.is_overdue(today) {
+            return false;
+        }
+
+        match &self.tag {
+            Some(tag) => {
+                let tag = normalize_tag(tag);
+                let nested = format!("{}/", tag);
+
+                task.tags
+                    .iter()
+                    .map(|task_tag| normalize_tag(task_tag))
+                    .any(|task_tag| task_tag == tag || task_tag.starts_with(&nested))
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ToggleError {
+    /// The entity doesn't exist or is not a zettel.
+    NotFound,
+    /// The source was changed since the caller has seen it.
+    Conflict,
+    /// There is no task in the given line.
+    NoTask,
+    Io(std::io::Error),
+}
+
+/// The hash callers have to present when changing a source, to make sure they don't overwrite changes they haven't seen.
+pub fn source_hash(source: &str) -> String {
+    hashing::Sha256::hash_string(source).as_string()
+}
+
+/// Flip the checkbox in a single line: open tasks are marked as done, all others as open again.
+fn toggle_checkbox(line: &str, open: bool) -> Option<String> {
+    let mut chars = line.chars().collect::<Vec<_>>();
+
+    // The first `[?]` in the line is the checkbox, the marker and any `>` come before it
+    let start = chars
+        .windows(3)
+        .position(|window| window[0] == '[' && window[2] == ']')?;
+
+    chars[start + 1] = if open { 'x' } else { ' ' };
+
+    Some(chars.into_iter().collect())
+}
+
+impl Vault {
+    /// Toggle the task in the given line of a zettel, returning the hash of the new source.
+    pub fn toggle_task(&self, id: &entity::Id, line: usize, expected_hash: &str) -> Result<String, ToggleError> {
+        let source = self.load_zettel_source(id).ok_or(ToggleError::NotFound)?;
+
+        if source_hash(&source) != expected_hash {
+            return Err(ToggleError::Conflict);
+        }
+
+        // Asking the parsed document makes sure that the line really holds a task, and not e.g. code
+        let open = self
+            .load_zettel(id)
+            .and_then(|zettel| {
+                zettel
+                    .body_as_document()?
+                    .bullet_point_at_line(line)?
+                    .task_info
+                    .as_ref()
+                    .map(TaskInfo::is_open)
+            })
+            .ok_or(ToggleError::NoTask)?;
+
+        let mut lines = source.split_inclusive('\n').map(str::to_string).collect::<Vec<_>>();
+        let toggled = lines
+            .get(line)
+            .and_then(|text| toggle_checkbox(text, open))
+            .ok_or(ToggleError::NoTask)?;
+
+        lines[line] = toggled;
+
+        let source = lines.concat();
+
+        self.save_zettel_source(id, &source).map_err(ToggleError::Io)?;
+
+        Ok(source_hash(&source))
+    }
+
+    /// All tasks of the vault, in the order of the entities and of the tasks within them.
+    pub fn tasks(&self) -> Vec<Task> {
+        use semantic::Scannable;
+
+        let mut tasks = Vec::new();
+
+        for id in self.list_entities() {
+            let entity = match self.load_entity(&id) {
+                Some(entity) => entity,
+                None => continue,
+            };
+
+            let mut found: Vec<semantic::Task> = Vec::new();
+            let mut entity_tags = Vec::new();
+
+            entity.iterate_info_items(&mut |item| match item {
+                semantic::InfoItem::Task(task) => found.push(task),
+                semantic::InfoItem::Tag(tag) => entity_tags.push(tag),
+                semantic::InfoItem::Link(_) => {}
+            });
+
+            // The tags written inside of tasks are scanned as well, they only
+            // belong to the task and not to its siblings
+            for tag in found.iter().flat_map(|task| &task.tags) {
+                if let Some(index) = entity_tags.iter().position(|entity_tag| entity_tag == tag) {
+                    entity_tags.remove(index);
+                }
+            }
+
+            for task in found {
+                let mut tags = task.tags;
+
+                for tag in &entity_tags {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+
+                tasks.push(Task {
+                    entity: id.clone(),
+                    info: task.info,
+                    text: task.text,
+                    tags,
+                });
+            }
+        }
+
+        tasks
+    }
+
+    pub fn filtered_tasks(&self, filter: &TaskFilter) -> Vec<Task> {
+        let today = chrono::Local::now().date_naive();
+
+        self.tasks()
+            .into_iter()
+            .filter(|task| filter.matches(task, today))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{source_hash, Task, TaskFilter, ToggleError};
+    use crate::core::{
+        config,
+        entity::{self, zettel::document::block::bullet_point::{Status, TaskInfo}},
+        vault::Vault,
+    };
+    use crate::util::testing::TempDir;
+
+    const NOTE: &str = "---\ntitle: Tasks\n---\n- [ ] top\n\t- [ ] nested\n\n> [!todo]\n> - [x] in a callout\n\n```\n- [ ] code\n```\n";
+
+    fn open_vault(dir: &TempDir) -> Vault {
+        dir.write("vault/note.md", NOTE);
+
+        config::ConfigBuilder::new()
+            .cache_path(dir.path().join("cache"))
+            .vault_path(dir.path().join("vault"))
+            .open_vault()
+            .unwrap()
+    }
+
+    fn date(text: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn task(status: Status, due: Option<&str>, tags: &[&str]) -> Task {
+        let mut info = TaskInfo::new(status);
+        info.due = due.map(date);
+
+        Task {
+            entity: entity::Id::Basic("note".to_string()),
+            info,
+            text: "Something".to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn filter_by_status_and_due_date() {
+        let today = date("2026-10-18");
+
+        let open = TaskFilter { open: true, ..TaskFilter::default() };
+        assert!(open.matches(&task(Status::Pending, None, &[]), today));
+        assert!(open.matches(&task(Status::InProgress, None, &[]), today));
+        assert!(!open.matches(&task(Status::Done, None, &[]), today));
+        assert!(!open.matches(&task(Status::Cancelled, None, &[]), today));
+
+        let overdue = TaskFilter { overdue: true, ..TaskFilter::default() };
+        assert!(overdue.matches(&task(Status::Pending, Some("2026-10-17"), &[]), today));
+        assert!(!overdue.matches(&task(Status::Pending, Some("2026-10-18"), &[]), today));
+        assert!(!overdue.matches(&task(Status::Pending, None, &[]), today));
+        assert!(!overdue.matches(&task(Status::Done, Some("2026-10-17"), &[]), today));
+
+        assert!(TaskFilter::default().matches(&task(Status::Done, Some("2020-01-01"), &[]), today));
+    }
+
+    #[test]
+    fn filter_by_nested_tag() {
+        let today = date("2026-10-18");
+        let filter = TaskFilter { tag: Some("#Project".to_string()), ..TaskFilter::default() };
+
+        assert!(filter.matches(&task(Status::Pending, None, &["project"]), today));
+        assert!(filter.matches(&task(Status::Pending, None, &["work", "project/athena"]), today));
+        assert!(!filter.matches(&task(Status::Pending, None, &["projects"]), today));
+        assert!(!filter.matches(&task(Status::Pending, None, &[]), today));
+    }
+
+    #[test]
+    fn bullet_points_know_their_line() {
+        use crate::core::entity::zettel::document::block::Block;
+
+        // Lines are counted from the start of the file, frontmatter included
+        let blocks = crate::formats::markdown::testing::parse_blocks(NOTE);
+
+        let Block::List(list) = &blocks[0].value else { panic!("{:?}", blocks) };
+        assert_eq!(list.items[0].span.start.line, 3);
+        assert_eq!(list.items[0].children[0].items[0].span.start.line, 4);
+
+        let Block::Callout(callout) = &blocks[1].value else { panic!("{:?}", blocks) };
+        let Block::List(list) = &callout.blocks[0].value else { panic!("{:?}", callout) };
+        assert_eq!(list.items[0].span.start.line, 7);
+    }
+
+    #[test]
+    fn toggle_nested_and_callout_tasks() {
+        let dir = TempDir::new("toggle");
+        let vault = open_vault(&dir);
+        let id = entity::Id::Basic("note".to_string());
+
+        let hash = vault.toggle_task(&id, 4, &source_hash(NOTE)).unwrap();
+        assert_eq!(dir.read("vault/note.md"), NOTE.replace("\t- [ ] nested", "\t- [x] nested"));
+        assert_eq!(hash, source_hash(&dir.read("vault/note.md")));
+
+        let hash = vault.toggle_task(&id, 7, &hash).unwrap();
+        assert!(dir.read("vault/note.md").contains("> - [ ] in a callout\n"));
+
+        vault.toggle_task(&id, 4, &hash).unwrap();
+        assert!(dir.read("vault/note.md").contains("\t- [ ] nested\n"));
+    }
+
+    #[test]
+    fn toggle_refuses_stale_hashes_and_lines_without_tasks() {
+        let dir = TempDir::new("toggle-errors");
+        let vault = open_vault(&dir);
+        let id = entity::Id::Basic("note".to_string());
+        let hash = source_hash(NOTE);
+
+        assert!(matches!(vault.toggle_task(&id, 3, "outdated"), Err(ToggleError::Conflict)));
+
+        // The frontmatter, an empty line, code and lines past the end hold no tasks
+        for line in [1, 5, 10, 100] {
+            assert!(matches!(vault.toggle_task(&id, line, &hash), Err(ToggleError::NoTask)), "{}", line);
+        }
+
+        assert!(matches!(
+            vault.toggle_task(&entity::Id::Basic("missing".to_string()), 3, &hash),
+            Err(ToggleError::NotFound)
+        ));
+
+        assert_eq!(dir.read("vault/note.md"), NOTE);
+    }
+}
@@ -1,15 +1,33 @@
-
+#[derive(Debug, Clone, Default)]
 pub struct Flags {
     pub has_zettels: bool,
+    /// Nothing in the volume may be modified through Athena.
+    pub is_read_only: bool,
+    /// The volume's entities are reachable by their ID, but they are neither listed nor indexed.
+    pub is_hidden: bool,
 }
 
 impl Flags {
     pub fn new() -> Self {
-        Flags { has_zettels: false }
+        Flags {
+            has_zettels: false,
+            is_read_only: false,
+            is_hidden: false,
+        }
     }
 
     pub fn with_zettels(mut self) -> Self {
         self.has_zettels = true;
         self
     }
+
+    pub fn with_read_only(mut self) -> Self {
+        self.is_read_only = true;
+        self
+    }
+
+    pub fn with_hidden(mut self) -> Self {
+        self.is_hidden = true;
+        self
+    }
 }
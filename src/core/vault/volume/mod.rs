@@ -1,5 +1,5 @@
 use crate::core::entity;
-use crate::core::vault::resource;
+use crate::core::vault::{event, resource};
 
 use super::caching;
 
@@ -7,12 +7,14 @@ pub mod flags;
 pub mod info;
 pub mod path;
 pub mod volumes;
+pub mod watcher;
 
 pub type VolumeId = crate::util::hashing::Sha256;
 pub type VolumePath = path::VolumePath;
 
 pub trait Volume {
     fn id(&self) -> &VolumeId;
+    fn flags(&self) -> &flags::Flags;
     fn list_resources<'a>(&'a self) -> Box<dyn Iterator<Item = resource::Resource> + 'a>;
 
     fn map_resource_func<'a, T>(
@@ -29,11 +31,20 @@ pub trait Volume {
         cache: &mut caching::GlobalCache,
     ) -> Option<resource::Resource>;
 
-    fn tick(&self);
+    /// All resources a link like `[[folder/name]]` could point to, the best match first.
+    fn resources_by_short_name(&self, name: &str) -> Vec<resource::Resource>;
+
+    /// Called periodically. Returns the changes to the volume's files since the last call.
+    fn tick(&self) -> Vec<event::FileEvent>;
+
+    /// Like `tick`, but only reports changes we have been notified about, without polling.
+    fn take_events(&self) -> Vec<event::FileEvent>;
 
     fn find_directory(&self, purpose: info::DirectoryPurpose) -> Option<std::path::PathBuf>;
 
     fn open_path(&self, path: &VolumePath) -> Result<Box<dyn std::io::Read>, std::io::Error>;
+
+    fn write_path(&self, path: &VolumePath, content: &[u8]) -> Result<(), std::io::Error>;
 }
 
 
@@ -50,6 +61,13 @@ impl Volume for VolumeEnum {
         }
     }
 
+    fn flags(&self) -> &flags::Flags {
+        match self {
+            VolumeEnum::Directory(v) => v.flags(),
+            VolumeEnum::Email(v) => v.flags(),
+        }
+    }
+
     fn list_resources<'a>(&'a self) -> Box<dyn Iterator<Item = resource::Resource> + 'a> {
         match self {
             VolumeEnum::Directory(v) => Box::new(v.list_resources()),
@@ -69,13 +87,27 @@ impl Volume for VolumeEnum {
         }
     }
 
-    fn tick(&self) {
+    fn resources_by_short_name(&self, name: &str) -> Vec<resource::Resource> {
+        match self {
+            VolumeEnum::Directory(v) => v.resources_by_short_name(name),
+            VolumeEnum::Email(v) => v.resources_by_short_name(name),
+        }
+    }
+
+    fn tick(&self) -> Vec<event::FileEvent> {
         match self {
             VolumeEnum::Directory(v) => v.tick(),
             VolumeEnum::Email(v) => v.tick(),
         }
     }
 
+    fn take_events(&self) -> Vec<event::FileEvent> {
+        match self {
+            VolumeEnum::Directory(v) => v.take_events(),
+            VolumeEnum::Email(v) => v.take_events(),
+        }
+    }
+
     fn find_directory(&self, purpose: info::DirectoryPurpose) -> Option<std::path::PathBuf> {
         match self {
             VolumeEnum::Directory(v) => v.find_directory(purpose),
@@ -89,6 +121,13 @@ impl Volume for VolumeEnum {
             VolumeEnum::Email(v) => v.open_path(path),
         }
     }
+
+    fn write_path(&self, path: &VolumePath, content: &[u8]) -> Result<(), std::io::Error> {
+        match self {
+            VolumeEnum::Directory(v) => v.write_path(path, content),
+            VolumeEnum::Email(v) => v.write_path(path, content),
+        }
+    }
 }
 
 pub type VolumeArc = std::sync::Arc<VolumeEnum>;
@@ -120,12 +159,28 @@ impl Volumes {
             .find_map(|volume| std::sync::Arc::get_mut(volume).filter(|v| v.id() == id))
     }
 
+    /// The volume new emails are imported into.
+    pub fn email_volume(&self) -> Option<&volumes::email::EmailVolume> {
+        self.vols.iter().find_map(|volume| match volume.as_ref() {
+            VolumeEnum::Email(email) => Some(email),
+            _ => None,
+        })
+    }
+
     pub fn list_resources<'a>(&'a self) -> impl Iterator<Item = resource::Resource> + 'a {
         self.vols
             .iter()
             .flat_map(|storage| storage.list_resources())
     }
 
+    /// Like `list_resources`, but skips hidden volumes.
+    pub fn list_visible_resources<'a>(&'a self) -> impl Iterator<Item = resource::Resource> + 'a {
+        self.vols
+            .iter()
+            .filter(|storage| !storage.flags().is_hidden)
+            .flat_map(|storage| storage.list_resources())
+    }
+
     pub fn map_resource_func<'a, T>(
         &'a self,
         func: impl Fn(&resource::Resource) -> T + Clone + 'a,
@@ -147,9 +202,18 @@ impl Volumes {
             .next()
     }
 
-    pub fn tick(&self) {
-        for storage in &self.vols {
-            storage.tick();
-        }
+    pub fn resources_by_short_name(&self, name: &str) -> Vec<resource::Resource> {
+        self.vols
+            .iter()
+            .flat_map(|storage| storage.resources_by_short_name(name))
+            .collect()
+    }
+
+    pub fn tick(&self) -> Vec<event::FileEvent> {
+        self.vols.iter().flat_map(|storage| storage.tick()).collect()
+    }
+
+    pub fn take_events(&self) -> Vec<event::FileEvent> {
+        self.vols.iter().flat_map(|storage| storage.take_events()).collect()
     }
 }
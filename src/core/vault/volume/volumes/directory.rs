@@ -1,15 +1,18 @@
 use crate::core::{
     entity,
-    vault::{caching, resource, volume::VolumeEnum},
+    vault::{caching, event, resource, volume::VolumeEnum},
 };
 
-use super::super::{flags, info, path, Volume, VolumeId, VolumePath};
+use super::super::{flags, info, path, watcher, Volume, VolumeId, VolumePath};
 
 pub struct DirectoryVolume {
     id: VolumeId,
     base_path: std::path::PathBuf,
     is_home: bool,
-    file_name_cache: std::collections::HashMap<String, std::path::PathBuf>,
+    flags: flags::Flags,
+    /// All files, grouped by their lowercase file name.
+    file_name_cache: std::sync::RwLock<std::collections::HashMap<String, Vec<std::path::PathBuf>>>,
+    watcher: watcher::Watcher,
 }
 
 impl Into<VolumeEnum> for DirectoryVolume {
@@ -31,40 +34,59 @@ impl DirectoryVolume {
         false
     }
 
-    pub fn new(base_path: std::path::PathBuf, _flags: flags::Flags) -> Self {
+    fn is_path_included(path: &std::path::Path) -> bool {
+        !Self::is_path_excluded(path)
+    }
+
+    pub fn new(base_path: std::path::PathBuf, flags: flags::Flags) -> Self {
         let id = VolumeId::hash_string(base_path.to_string_lossy().to_string());
 
-        let file_name_cache = {
-            let mut files = std::collections::HashMap::new();
-            let mut dirs = vec![base_path.clone()];
+        let watcher = watcher::Watcher::new(base_path.clone(), Self::is_path_included);
 
-            while let Some(dir) = dirs.pop() {
-                for entry in std::fs::read_dir(&dir).unwrap() {
-                    let entry = entry.unwrap();
-                    let path = entry.path();
+        let volume = Self {
+            id,
+            base_path,
+            is_home: false,
+            flags,
+            file_name_cache: std::sync::RwLock::new(std::collections::HashMap::new()),
+            watcher,
+        };
 
-                    if Self::is_path_excluded(&path) {
-                        continue;
-                    }
+        volume.rebuild_file_name_cache();
 
-                    if path.is_dir() {
-                        dirs.push(path);
-                    } else if path.is_file() {
-                        let name = path.file_name().unwrap().to_string_lossy().to_string();
-                        files.insert(name, path);
-                    }
-                }
+        volume
+    }
+
+    fn rebuild_file_name_cache(&self) {
+        let mut files: std::collections::HashMap<String, Vec<std::path::PathBuf>> = std::collections::HashMap::new();
+
+        for path in self.watcher.files() {
+            if let Some(name) = path.file_name() {
+                files.entry(name.to_string_lossy().to_lowercase()).or_default().push(path);
             }
+        }
 
-            files
-        };
+        if let Ok(mut cache) = self.file_name_cache.write() {
+            *cache = files;
+        }
+    }
 
-        Self {
-            id,
-            base_path,
-            is_home: false,
-            file_name_cache,
+    fn process_changes(&self, changes: Vec<watcher::Change>) -> Vec<event::FileEvent> {
+        if changes.iter().any(|change| change.kind != watcher::ChangeKind::Modified) {
+            self.rebuild_file_name_cache();
         }
+
+        changes
+            .into_iter()
+            .filter_map(|change| {
+                let path = self.construct_volume_path(&change.path)?;
+                Some(change.into_file_event(path))
+            })
+            .collect()
+    }
+
+    fn resource_at(&self, path: path::VolumePath) -> resource::Resource {
+        resource::Resource::from_path(path).with_zettels(self.flags.has_zettels)
     }
 
     fn construct_volume_path(&self, path: &std::path::Path) -> Option<path::VolumePath> {
@@ -90,20 +112,7 @@ impl DirectoryVolume {
     }
 
     fn list_files(&self) -> impl Iterator<Item = std::path::PathBuf> {
-        fn condition(
-            entry: Result<walkdir::DirEntry, walkdir::Error>,
-        ) -> Option<std::path::PathBuf> {
-            let entry = entry.unwrap();
-            if entry.file_type().is_file() && !DirectoryVolume::is_path_excluded(entry.path()) {
-                Some(entry.into_path())
-            } else {
-                None
-            }
-        }
-
-        walkdir::WalkDir::new(&self.base_path)
-            .into_iter()
-            .filter_map(condition)
+        self.watcher.files().into_iter()
     }
 
     pub fn map_resource_func<'a, T>(
@@ -113,41 +122,70 @@ impl DirectoryVolume {
         self.list_resources().map(move |resource| func(&resource))
     }
 
-    fn file_if_exists<S: ToString>(&self, name: S) -> Option<std::path::PathBuf> {
-        let name = name.to_string();
-        self.file_name_cache.get(&name).cloned()
-    }
+    /// The path relative to the volume, with forward slashes like in links.
+    fn relative_link_path(&self, path: &std::path::Path) -> String {
+        let relative = path.strip_prefix(&self.base_path).unwrap_or(path);
 
-    fn file_by_short_name(&self, name: &str) -> Option<std::path::PathBuf> {
-        // Try different formats: .zson, .md
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
 
-        let extensions = resource::Type::all_extensions();
+    /// All files a link like `[[index]]` or `[[projects/athena/index]]` could point to, the best match first.
+    ///
+    /// Like in Obsidian, the name is compared case-insensitively and may leave out the file extension
+    /// and any number of leading folders. Matches with the exact case come first, then the ones closest
+    /// to the root of the volume.
+    fn files_by_short_name(&self, name: &str) -> Vec<std::path::PathBuf> {
+        let name = name.trim_start_matches("./").trim_start_matches('/');
 
-        // If the name contains a dot, and the dot is not the leading character, then we assume
-        // that the name already contains a file extension.
-        let has_file_extension = name.contains('.') && !name.starts_with('.');
+        if name.is_empty() {
+            return Vec::new();
+        }
 
-        let variants = if has_file_extension {
-            vec![name.to_string()]
-        } else {
-            extensions
-                .iter()
-                .map(|ext| format!("{}.{}", name, ext))
-                .collect()
+        // The name might already contain a file extension, e.g. `nijakow.png`
+        let variants = std::iter::once(name.to_string())
+            .chain(
+                resource::Type::all_extensions()
+                    .iter()
+                    .map(|ext| format!("{}.{}", name, ext)),
+            )
+            .collect::<Vec<_>>();
+
+        let cache = match self.file_name_cache.read() {
+            Ok(cache) => cache,
+            Err(_) => return Vec::new(),
         };
 
-        for name in variants {
-            if let Some(path) = self.file_if_exists(&name) {
-                return Some(path);
+        let mut matches = Vec::new();
+
+        for variant in variants {
+            let file_name = variant.rsplit('/').next().unwrap_or(&variant).to_lowercase();
+
+            for path in cache.get(&file_name).into_iter().flatten() {
+                let relative = self.relative_link_path(path);
+                let lowercase = relative.to_lowercase();
+                let wanted = variant.to_lowercase();
+
+                let matches_path = lowercase == wanted || lowercase.ends_with(&format!("/{}", wanted));
+
+                if matches_path && !matches.iter().any(|(_, other)| other == path) {
+                    let exact = relative == variant || relative.ends_with(&format!("/{}", variant));
+                    let depth = relative.matches('/').count();
+
+                    matches.push(((!exact, depth, relative), path.clone()));
+                }
             }
         }
 
-        None
+        matches.sort();
+        matches.into_iter().map(|(_, path)| path).collect()
     }
 
     fn resource_by_short_name(&self, name: &str) -> Option<resource::Resource> {
-        self.file_by_short_name(name)
-            .map(|path| resource::Resource::from_path(self.construct_volume_path(&path).unwrap()))
+        self.resources_by_short_name(name).into_iter().next()
     }
 }
 
@@ -156,10 +194,14 @@ impl Volume for DirectoryVolume {
         &self.id
     }
 
+    fn flags(&self) -> &flags::Flags {
+        &self.flags
+    }
+
     fn list_resources<'a>(&'a self) -> Box<dyn Iterator<Item = resource::Resource> + 'a> {
         Box::new(self.list_files().map(move |path| {
             let vp = self.construct_volume_path(&path).unwrap();
-            resource::Resource::from_path(vp)
+            self.resource_at(vp)
         }))
     }
 
@@ -183,10 +225,25 @@ impl Volume for DirectoryVolume {
             }
             entity::Id::Email(_) => None, // TODO!
             entity::Id::Basic(name) => self.resource_by_short_name(name),
+            entity::Id::Attachment(..) => None,
         }
     }
 
-    fn tick(&self) {}
+    fn resources_by_short_name(&self, name: &str) -> Vec<resource::Resource> {
+        self.files_by_short_name(name)
+            .into_iter()
+            .filter_map(|path| self.construct_volume_path(&path))
+            .map(|vp| self.resource_at(vp))
+            .collect()
+    }
+
+    fn tick(&self) -> Vec<event::FileEvent> {
+        self.process_changes(self.watcher.tick())
+    }
+
+    fn take_events(&self) -> Vec<event::FileEvent> {
+        self.process_changes(self.watcher.take_changes())
+    }
 
     fn find_directory(&self, purpose: info::DirectoryPurpose) -> Option<std::path::PathBuf> {
         match purpose {
@@ -206,6 +263,80 @@ impl Volume for DirectoryVolume {
             std::io::Error::new(std::io::ErrorKind::NotFound, "Path not found in volume")
         })?;
 
-        std::fs::File::open(translated).map(|f| Box::new(f) as Box<dyn std::io::Read>)
+        resource::open_file(&translated)
+    }
+
+    fn write_path(&self, path: &VolumePath, content: &[u8]) -> Result<(), std::io::Error> {
+        use std::io::Write;
+
+        if self.flags.is_read_only {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "Volume is read-only",
+            ));
+        }
+
+        let translated = self.reconstruct_full_path(path).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Path not found in volume")
+        })?;
+
+        let file_name = translated.file_name().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path has no file name")
+        })?;
+
+        // Write into a hidden sibling first and rename it over the original afterwards.
+        // The rename is atomic as long as both files live on the same filesystem, and
+        // the leading dot keeps the temporary file out of `list_files`.
+        let temp_path = translated.with_file_name(format!(".{}.athena-tmp", file_name.to_string_lossy()));
+
+        let result = std::fs::File::create(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(content)?;
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&temp_path, &translated));
+
+        if result.is_err() {
+            std::fs::remove_file(&temp_path).ok();
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirectoryVolume;
+    use crate::core::vault::volume::flags::Flags;
+    use crate::util::testing::TempDir;
+
+    fn short_names(volume: &DirectoryVolume, name: &str) -> Vec<String> {
+        volume
+            .files_by_short_name(name)
+            .iter()
+            .map(|path| volume.relative_link_path(path))
+            .collect()
+    }
+
+    #[test]
+    fn files_by_short_name() {
+        let dir = TempDir::new("short-names");
+
+        for file in ["Index.md", "projects/index.md", "projects/athena/Index.md", "image.png", ".hidden/index.md"] {
+            dir.write(file, "");
+        }
+
+        let volume = DirectoryVolume::new(dir.path().to_path_buf(), Flags::new().with_zettels());
+
+        // The exact case wins over the depth, hidden files are never found
+        assert_eq!(short_names(&volume, "Index"), vec!["Index.md", "projects/athena/Index.md", "projects/index.md"]);
+        assert_eq!(short_names(&volume, "index"), vec!["projects/index.md", "Index.md", "projects/athena/Index.md"]);
+
+        assert_eq!(short_names(&volume, "athena/INDEX.md"), vec!["projects/athena/Index.md"]);
+        assert_eq!(short_names(&volume, "./Image"), vec!["image.png"]);
+
+        assert!(short_names(&volume, "dex").is_empty());
+        assert!(short_names(&volume, "jects/index").is_empty());
+        assert!(short_names(&volume, "").is_empty());
     }
 }
@@ -1,7 +1,7 @@
 use crate::{
     core::vault::{
-        resource,
-        volume::{path, Volume, VolumeEnum, VolumeId},
+        event, resource,
+        volume::{flags, path, watcher, Volume, VolumeEnum, VolumeId},
     },
     util::hashing,
 };
@@ -9,6 +9,8 @@ use crate::{
 pub struct EmailVolume {
     id: VolumeId,
     base_path: std::path::PathBuf,
+    flags: flags::Flags,
+    watcher: watcher::Watcher,
 }
 
 impl Into<VolumeEnum> for EmailVolume {
@@ -18,9 +20,30 @@ impl Into<VolumeEnum> for EmailVolume {
 }
 
 impl EmailVolume {
-    pub fn new(base_path: std::path::PathBuf) -> Self {
+    pub fn new(base_path: std::path::PathBuf, flags: flags::Flags) -> Self {
         let id = VolumeId::hash_string(base_path.to_string_lossy().to_string());
-        Self { id, base_path }
+        let watcher = watcher::Watcher::new(base_path.clone(), Self::is_email_path);
+        Self {
+            id,
+            base_path,
+            flags,
+            watcher,
+        }
+    }
+
+    fn is_email_path(path: &std::path::Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "eml")
+            || resource::transparently_compressed_path(path).is_some()
+    }
+
+    fn process_changes(&self, changes: Vec<watcher::Change>) -> Vec<event::FileEvent> {
+        changes
+            .into_iter()
+            .filter_map(|change| {
+                let path = self.construct_volume_path(&change.path)?;
+                Some(change.into_file_event(path))
+            })
+            .collect()
     }
 
     pub fn base_path(&self) -> &std::path::PathBuf {
@@ -49,13 +72,19 @@ impl EmailVolume {
         Some(full_path)
     }
 
+    /// Where an email with the given hash is stored: `<base>/<first two hex chars>/<sha>.<ext>`.
+    fn sharded_path(&self, hash: &hashing::Sha256, ext: &str) -> std::path::PathBuf {
+        let mut path = self.base_path.clone();
+        let stringified = hash.as_string();
+        let first_two_chars = &stringified[0..2];
+        path.push(first_two_chars);
+        path.push(format!("{}.{}", stringified, ext));
+        path
+    }
+
     fn find_path_for_email(&self, hash: &hashing::Sha256) -> Option<std::path::PathBuf> {
         let try_extension = |ext: &str| {
-            let mut path = self.base_path.clone();
-            let stringified = hash.as_string();
-            let first_two_chars = &stringified[0..2];
-            path.push(first_two_chars);
-            path.push(format!("{}.{}", hash.as_string(), ext));
+            let path = self.sharded_path(hash, ext);
             if path.exists() {
                 Some(path)
             } else {
@@ -63,8 +92,60 @@ impl EmailVolume {
             }
         };
 
-        // Try .eml (and later perhaps .eml.gz)
-        try_extension("eml")
+        try_extension("eml").or_else(|| try_extension("eml.gz"))
+    }
+
+    pub fn contains_email(&self, hash: &hashing::Sha256) -> bool {
+        self.sharded_path(hash, "eml").exists() || self.sharded_path(hash, "eml.gz").exists()
+    }
+
+    /// Store a raw RFC 822 message under its hash, optionally gzip-compressed.
+    /// Returns `None` if the message was already present.
+    pub fn store_email(&self, content: &[u8], compress: bool) -> Result<Option<hashing::Sha256>, std::io::Error> {
+        use std::io::Write;
+
+        if self.flags.is_read_only {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "Volume is read-only",
+            ));
+        }
+
+        let hash = hashing::Sha256::hash_bytes(content);
+
+        if self.contains_email(&hash) {
+            return Ok(None);
+        }
+
+        let path = self.sharded_path(&hash, if compress { "eml.gz" } else { "eml" });
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first, so that the watcher never sees a partial message
+        let temp_path = path.with_extension("athena-tmp");
+
+        let result = (|| {
+            let mut file = std::fs::File::create(&temp_path)?;
+
+            if compress {
+                let mut encoder = flate2::write::GzEncoder::new(&mut file, flate2::Compression::default());
+                encoder.write_all(content)?;
+                encoder.finish()?;
+            } else {
+                file.write_all(content)?;
+            }
+
+            file.sync_all()?;
+            std::fs::rename(&temp_path, &path)
+        })();
+
+        if result.is_err() {
+            std::fs::remove_file(&temp_path).ok();
+        }
+
+        result.map(|_| Some(hash))
     }
 }
 
@@ -73,28 +154,16 @@ impl Volume for EmailVolume {
         &self.id
     }
 
+    fn flags(&self) -> &flags::Flags {
+        &self.flags
+    }
+
     fn list_resources<'a>(&'a self) -> Box<dyn Iterator<Item = resource::Resource> + 'a> {
-        // Use walkdir crate to list all .eml and .eml.gz files
-        let files = walkdir::WalkDir::new(&self.base_path)
-            .into_iter()
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
-                if entry.file_type().is_file() {
-                    let path = entry.into_path();
-                    let ext = path.extension()?.to_string_lossy();
-                    if ext == "eml" {
-                        Some(path)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            });
-        
+        let files = self.watcher.files().into_iter();
+
         Box::new(files.map(move |path| {
             let vp = self.construct_volume_path(&path).unwrap();
-            resource::Resource::from_path(vp)
+            resource::Resource::from_path(vp).with_zettels(self.flags.has_zettels)
         }))
     }
 
@@ -109,16 +178,26 @@ impl Volume for EmailVolume {
             crate::core::entity::Id::Email(sha256) => {
                 if let Some(path) = self.find_path_for_email(sha256) {
                     let vp = self.construct_volume_path(&path).unwrap();
-                    return Some(resource::Resource::from_path(vp));
+                    return Some(resource::Resource::from_path(vp).with_zettels(self.flags.has_zettels));
                 }
                 None
             }
             crate::core::entity::Id::Basic(_) => None,
+            crate::core::entity::Id::Attachment(..) => None,
         }
     }
 
-    fn tick(&self) {
-        // Do nothing
+    fn resources_by_short_name(&self, _name: &str) -> Vec<resource::Resource> {
+        // Emails are only addressed by their hash
+        Vec::new()
+    }
+
+    fn tick(&self) -> Vec<event::FileEvent> {
+        self.process_changes(self.watcher.tick())
+    }
+
+    fn take_events(&self) -> Vec<event::FileEvent> {
+        self.process_changes(self.watcher.take_changes())
     }
 
     fn find_directory(
@@ -136,6 +215,18 @@ impl Volume for EmailVolume {
             std::io::Error::new(std::io::ErrorKind::NotFound, "Path not found in volume")
         })?;
 
-        std::fs::File::open(translated).map(|f| Box::new(f) as Box<dyn std::io::Read>)
+        resource::open_file(&translated)
+    }
+
+    fn write_path(
+        &self,
+        _path: &crate::core::vault::volume::VolumePath,
+        _content: &[u8],
+    ) -> Result<(), std::io::Error> {
+        // Emails are content-addressed, so changing one would invalidate its ID
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "Email volumes are read-only",
+        ))
     }
 }
@@ -0,0 +1,239 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+use crate::core::vault::event;
+
+use super::VolumePath;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<std::time::SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: PathBuf,
+}
+
+impl Change {
+    pub fn into_file_event(self, path: VolumePath) -> event::FileEvent {
+        match self.kind {
+            ChangeKind::Created => event::FileEvent::Created(path),
+            ChangeKind::Modified => event::FileEvent::Modified(path),
+            ChangeKind::Deleted => event::FileEvent::Deleted(path),
+        }
+    }
+}
+
+type Notifications = mpsc::Receiver<notify::Result<notify::Event>>;
+
+/// Keeps track of the files below a directory.
+///
+/// Changes are picked up through inotify (or whatever `notify` uses on this platform). If the
+/// watch can't be established, we fall back to rescanning the whole directory on every tick.
+pub struct Watcher {
+    base_path: PathBuf,
+    filter: fn(&Path) -> bool,
+    files: Mutex<HashMap<PathBuf, FileStamp>>,
+    watcher: Option<notify::RecommendedWatcher>,
+    notifications: Option<Mutex<Notifications>>,
+}
+
+impl Watcher {
+    /// Start tracking all files below `base_path` for which `filter` returns true.
+    pub fn new(base_path: PathBuf, filter: fn(&Path) -> bool) -> Self {
+        let files = Self::scan(&base_path, filter);
+
+        let (watcher, notifications) = match Self::start_watching(&base_path) {
+            Ok((watcher, notifications)) => (Some(watcher), Some(Mutex::new(notifications))),
+            Err(e) => {
+                eprintln!("Unable to watch {:?}, falling back to polling: {}", base_path, e);
+                (None, None)
+            }
+        };
+
+        Self {
+            base_path,
+            filter,
+            files: Mutex::new(files),
+            watcher,
+            notifications,
+        }
+    }
+
+    fn start_watching(base_path: &Path) -> notify::Result<(notify::RecommendedWatcher, Notifications)> {
+        use notify::Watcher;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        watcher.watch(base_path, notify::RecursiveMode::Recursive)?;
+
+        Ok((watcher, receiver))
+    }
+
+    fn scan(path: &Path, filter: fn(&Path) -> bool) -> HashMap<PathBuf, FileStamp> {
+        walkdir::WalkDir::new(path)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && filter(entry.path()))
+            .filter_map(|entry| {
+                let stamp = FileStamp::of(&entry.metadata().ok()?);
+                Some((entry.into_path(), stamp))
+            })
+            .collect()
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// All files we currently know about, in a stable order.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = match self.files.lock() {
+            Ok(files) => files.keys().cloned().collect::<Vec<_>>(),
+            Err(_) => vec![],
+        };
+
+        files.sort();
+        files
+    }
+
+    fn update_file(
+        &self,
+        files: &mut HashMap<PathBuf, FileStamp>,
+        path: &Path,
+        stamp: Option<FileStamp>,
+        changes: &mut Vec<Change>,
+    ) {
+        let stamp = stamp.filter(|_| (self.filter)(path));
+
+        let kind = match (files.get(path).copied(), stamp) {
+            (None, Some(stamp)) => {
+                files.insert(path.to_path_buf(), stamp);
+                ChangeKind::Created
+            }
+            (Some(old), Some(stamp)) if old != stamp => {
+                files.insert(path.to_path_buf(), stamp);
+                ChangeKind::Modified
+            }
+            (Some(_), None) => {
+                files.remove(path);
+                ChangeKind::Deleted
+            }
+            _ => return,
+        };
+
+        changes.push(Change {
+            kind,
+            path: path.to_path_buf(),
+        });
+    }
+
+    /// Compare a single path (which may also be a directory) against what we know about it.
+    fn reconcile_path(&self, files: &mut HashMap<PathBuf, FileStamp>, path: &Path, changes: &mut Vec<Change>) {
+        let mut vanished = files
+            .keys()
+            .filter(|known| known.starts_with(path))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {
+                for (file, stamp) in Self::scan(path, self.filter) {
+                    vanished.remove(&file);
+                    self.update_file(files, &file, Some(stamp), changes);
+                }
+            }
+            Ok(metadata) if metadata.is_file() => {
+                vanished.remove(path);
+                self.update_file(files, path, Some(FileStamp::of(&metadata)), changes);
+            }
+            _ => {}
+        }
+
+        for file in vanished {
+            self.update_file(files, &file, None, changes);
+        }
+    }
+
+    /// Rescan the whole directory and report everything that changed since the last scan.
+    pub fn poll(&self) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        if let Ok(mut files) = self.files.lock() {
+            self.reconcile_path(&mut files, &self.base_path, &mut changes);
+        }
+
+        changes
+    }
+
+    /// Report the changes to all paths the watcher told us about since the last call.
+    pub fn take_changes(&self) -> Vec<Change> {
+        let notifications = match &self.notifications {
+            Some(notifications) => notifications,
+            None => return vec![],
+        };
+
+        let mut touched = BTreeSet::new();
+        let mut rescan = false;
+
+        if let Ok(receiver) = notifications.lock() {
+            for result in receiver.try_iter() {
+                match result {
+                    Ok(event) => {
+                        rescan |= event.need_rescan();
+
+                        if !matches!(event.kind, notify::EventKind::Access(_)) {
+                            touched.extend(event.paths);
+                        }
+                    }
+                    Err(_) => rescan = true,
+                }
+            }
+        }
+
+        // If the kernel dropped events, we no longer know what happened
+        if rescan {
+            return self.poll();
+        }
+
+        let mut changes = Vec::new();
+
+        if let Ok(mut files) = self.files.lock() {
+            for path in touched {
+                self.reconcile_path(&mut files, &path, &mut changes);
+            }
+        }
+
+        changes
+    }
+
+    /// Called periodically. Polls the directory if we are not able to watch it.
+    pub fn tick(&self) -> Vec<Change> {
+        if self.is_watching() {
+            self.take_changes()
+        } else {
+            self.poll()
+        }
+    }
+}
@@ -0,0 +1,18 @@
+/*
+ * Emails are parsed with the `mail-parser` crate, which takes care of
+ * transfer encodings (quoted-printable, base64), RFC 2047 encoded words,
+ * charsets and nested multipart bodies.
+ */
+
+pub type Message = mail_parser::Message<'static>;
+
+pub fn parse_rfc822(content: &[u8]) -> Result<Message, ()> {
+    mail_parser::MessageParser::default()
+        .parse(content)
+        .map(|message| message.into_owned())
+        .ok_or(())
+}
+
+pub fn parse_email(content: crate::core::vault::resource::file::FileContent) -> Result<Message, ()> {
+    parse_rfc822(content.content())
+}
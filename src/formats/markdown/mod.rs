@@ -1,8 +1,13 @@
 
 use url;
 
+pub use crate::util::span::{Position, Span, Spanned};
+
 pub mod parser;
 
+#[cfg(test)]
+pub(crate) mod testing;
+
 #[derive(Debug, Clone)]
 pub enum LinkKind {
     Internal,
@@ -30,10 +35,19 @@ impl LinkTarget {
 }
 
 
+/// The part of a note a link points to, written after a `#`.
+#[derive(Debug, Clone)]
+pub enum Anchor {
+    Heading(String),
+    /// A block marked with `^id`, without the `^`.
+    Block(String),
+}
+
 #[derive(Debug)]
 pub struct Link {
     pub kind: LinkKind,
     pub target: LinkTarget,
+    pub anchor: Option<Anchor>,
     pub title: Option<Nodes>,
 }
 
@@ -42,6 +56,7 @@ impl Link {
         Link {
             kind,
             target,
+            anchor: None,
             title: None,
         }
     }
@@ -50,9 +65,15 @@ impl Link {
         Link {
             kind,
             target,
+            anchor: None,
             title: Some(title),
         }
     }
+
+    pub fn with_anchor(mut self, anchor: Option<Anchor>) -> Link {
+        self.anchor = anchor;
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -61,16 +82,24 @@ pub enum Node {
     Text(String),
     Bold(Box<Node>),
     Italic(Box<Node>),
+    Strikethrough(Box<Node>),
+    Highlight(Box<Node>),
     Link {
         embed: bool,
         link: Link,
     },
     Code(String),
+    /// `$tex$`, without the dollar signs.
+    Math(String),
+    /// `%%text%%`, which is not shown.
+    Comment(String),
+    /// A character written with a backslash in front, so that it doesn't start any markup.
+    Escaped(char),
     Tag(String),
     Nodes(Nodes),
 }
 
-pub type Nodes = Vec<Node>;
+pub type Nodes = Vec<Spanned<Node>>;
 
 #[derive(Debug)]
 pub struct Heading(pub u8, pub Nodes);
@@ -79,22 +108,91 @@ pub struct Heading(pub u8, pub Nodes);
 pub enum TaskStatus {
     Completed,
     Pending,
+    Cancelled,
+    InProgress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    Unordered,
+    /// The number written in front of the item, e.g. `3` for `3.` or `3)`.
+    Ordered(u64),
+}
+
+impl ListKind {
+    /// Whether two items can be part of the same list.
+    pub fn continues(&self, other: &ListKind) -> bool {
+        matches!(
+            (self, other),
+            (ListKind::Unordered, ListKind::Unordered) | (ListKind::Ordered(_), ListKind::Ordered(_))
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ListItem {
+    pub task: Option<TaskStatus>,
+    pub nodes: Nodes,
+    /// Lists that are indented below this item.
+    pub children: Vec<List>,
+    /// The line the item is written in, without the nested lists.
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct List {
+    /// The kind of the first item, so ordered lists carry their start number.
+    pub kind: ListKind,
+    /// 0 for a list at the top level, 1 for a list nested in one of its items, ...
+    pub depth: usize,
+    pub items: Vec<ListItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug)]
+pub struct Table {
+    /// One entry per column, `None` if the delimiter row doesn't specify an alignment.
+    pub alignments: Vec<Option<Alignment>>,
+    pub header: Vec<Nodes>,
+    /// Every row has exactly as many cells as the header.
+    pub rows: Vec<Vec<Nodes>>,
+}
+
+/// Whether a foldable callout starts out expanded (`+`) or collapsed (`-`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalloutFold {
+    Expanded,
+    Collapsed,
 }
 
 #[derive(Debug)]
-pub struct BulletPoint(pub Option<TaskStatus>, pub Nodes);
+pub struct Callout {
+    /// The kind written as `[!kind]`, `None` for a plain quote.
+    pub kind: Option<String>,
+    pub fold: Option<CalloutFold>,
+    /// The text behind the kind on the first line.
+    pub title: Nodes,
+    pub blocks: Blocks,
+}
 
 #[derive(Debug)]
 pub enum Block {
     ThematicBreak,
     Heading(Heading),
-    BulletPoint(BulletPoint),
+    List(List),
+    Table(Table),
     Code(Option<String>, String),
-    Callout(Option<String>, Blocks),
+    Callout(Callout),
     Nodes(Nodes),
 }
 
-pub type Blocks = Vec<Block>;
+pub type Blocks = Vec<Spanned<Block>>;
 
 /*
  * A markdown document.
@@ -132,13 +230,16 @@ pub fn parse_obsidian_markdown(
     match content.extract_content_as_string() {
         Some(content) => {
 
-            let (metadata, content) = crate::util::split_metadata_from_content(content);
+            let (metadata, body) = crate::util::split_metadata_from_content(content.clone());
+
+            // The body is what remains after the frontmatter, so positions are counted from there on
+            let start = Position::default().advanced_by(&content[..content.len() - body.len()]);
 
             let metadata = metadata
                 .and_then(|m| yaml_rust2::YamlLoader::load_from_str(&m).ok())
                 .and_then(|mut docs| docs.pop());
 
-            match crate::formats::markdown::parser::parse_document(content) {
+            match crate::formats::markdown::parser::parse_document_at(body, start) {
                 Ok(document) => {
                     Ok(crate::formats::markdown::ObsidianDocument {
                         head: metadata,
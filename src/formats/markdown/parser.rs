@@ -1,7 +1,7 @@
 
 use crate::formats::markdown;
 
-use super::{Link, Node, Nodes};
+use super::{Link, Node, Nodes, Position, Span, Spanned};
 
 #[derive(Debug)]
 pub enum ParseError {}
@@ -38,12 +38,19 @@ fn count_leading_chars(s: &str, c: char) -> usize {
     s.chars().take_while(|&x| x == c).count()
 }
 
+fn count_leading_chars_matching<F: Fn(char) -> bool>(s: &str, f: F) -> usize {
+    s.chars().take_while(|&x| f(x)).count()
+}
+
 fn split_task_string(s: &str) -> (Option<markdown::TaskStatus>, String) {
     // Check for index 0 and 2 being [ and ] respectively
 
     if s.chars().nth(0) == Some('[') && s.chars().nth(2) == Some(']') {
+        // Unknown markers count as completed, like Obsidian renders them
         let status = match s.chars().nth(1) {
             Some(' ') => Some(markdown::TaskStatus::Pending),
+            Some('-') => Some(markdown::TaskStatus::Cancelled),
+            Some('/') => Some(markdown::TaskStatus::InProgress),
             _ => Some(markdown::TaskStatus::Completed),
         };
 
@@ -55,9 +62,53 @@ fn split_task_string(s: &str) -> (Option<markdown::TaskStatus>, String) {
     }
 }
 
+/// Decode the `%20`s of Markdown link targets, invalid sequences are kept as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Split `note#Heading` and `note#^block` into the note and the anchor.
+fn split_anchor(target: &str) -> (String, Option<markdown::Anchor>) {
+    match target.split_once('#') {
+        Some((note, anchor)) if !anchor.is_empty() => {
+            let anchor = match anchor.strip_prefix('^') {
+                Some(block) => markdown::Anchor::Block(block.to_string()),
+                None => markdown::Anchor::Heading(anchor.to_string()),
+            };
+
+            (note.to_string(), Some(anchor))
+        }
+        Some((note, _)) => (note.to_string(), None),
+        None => (target.to_string(), None),
+    }
+}
+
 pub enum PreParsed {
-    Parsed(Box<markdown::Block>),
-    Unparsed(Box<Vec<String>>),
+    Parsed(Box<Spanned<markdown::Block>>),
+    Unparsed(Box<Vec<SourceLine>>),
 }
 
 
@@ -65,6 +116,8 @@ pub enum PreParsed {
 struct ParagraphFlags {
     bold: bool,
     italic: bool,
+    strikethrough: bool,
+    highlight: bool,
     link: bool,
 }
 
@@ -73,6 +126,8 @@ impl ParagraphFlags {
         ParagraphFlags {
             bold: false,
             italic: false,
+            strikethrough: false,
+            highlight: false,
             link: false,
         }
     }
@@ -91,6 +146,20 @@ impl ParagraphFlags {
         }
     }
 
+    fn with_strikethrough(&self) -> ParagraphFlags {
+        ParagraphFlags {
+            strikethrough: true,
+            ..*self
+        }
+    }
+
+    fn with_highlight(&self) -> ParagraphFlags {
+        ParagraphFlags {
+            highlight: true,
+            ..*self
+        }
+    }
+
     fn with_link(&self) -> ParagraphFlags {
         ParagraphFlags {
             link: true,
@@ -119,15 +188,92 @@ impl LittleParser {
     }
 }
 
+/// A line of the source, without its line break.
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    text: String,
+    start: Position,
+}
+
+impl SourceLine {
+    fn end(&self) -> Position {
+        self.start.advanced_by(&self.text)
+    }
+
+    /// What remains of the line after the first `bytes` bytes.
+    fn skip(&self, bytes: usize) -> SourceLine {
+        SourceLine {
+            text: self.text[bytes..].to_string(),
+            start: self.start.advanced_by(&self.text[..bytes]),
+        }
+    }
+
+    /// What remains of the line after the first `count` chars.
+    fn skip_chars(&self, count: usize) -> SourceLine {
+        self.skip(self.text.char_indices().nth(count).map_or(self.text.len(), |(index, _)| index))
+    }
+
+    /// The first `bytes` bytes of the line.
+    fn prefix(&self, bytes: usize) -> SourceLine {
+        SourceLine {
+            text: self.text[..bytes].to_string(),
+            start: self.start,
+        }
+    }
+
+    /// The given suffix of the line, at its place in the source.
+    fn suffix(&self, suffix: &str) -> SourceLine {
+        self.skip(self.text.len() - suffix.len())
+    }
+}
+
 struct ParagraphParser {
     chars: Vec<char>,
+    /// Where each of the chars is in the source, plus the position after the last one.
+    positions: Vec<Position>,
 }
 
 impl ParagraphParser {
     pub fn for_string<S: ToString>(string: S) -> ParagraphParser {
-        let chars: Vec<char> = string.to_string().chars().collect();
+        let string = string.to_string();
 
-        ParagraphParser { chars }
+        ParagraphParser::for_lines(&[SourceLine {
+            text: string,
+            start: Position::default(),
+        }])
+    }
+
+    /// Parse the lines as one paragraph, joined by line breaks.
+    fn for_lines(lines: &[SourceLine]) -> ParagraphParser {
+        let mut chars = Vec::new();
+        let mut positions = Vec::new();
+
+        for (index, line) in lines.iter().enumerate() {
+            if index > 0 {
+                chars.push('\n');
+                positions.push(lines[index - 1].end());
+            }
+
+            let mut position = line.start;
+
+            for c in line.text.chars() {
+                chars.push(c);
+                positions.push(position);
+                position = position.advanced_by(c.encode_utf8(&mut [0; 4]));
+            }
+        }
+
+        positions.push(lines.last().map(SourceLine::end).unwrap_or_default());
+
+        ParagraphParser { chars, positions }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.positions[start], self.positions[end])
+    }
+
+    fn text(&self) -> String {
+        self.chars.iter().collect()
     }
 
     fn at_end(&self, index: usize) -> bool {
@@ -194,6 +340,74 @@ impl ParagraphParser {
         )
     }
 
+    fn parse_strikethrough_extra_wrap(&self, nodes: Nodes, i: usize) -> Option<ParseReturn> {
+        Some(ParseReturn(Node::Strikethrough(Box::new(Node::Nodes(nodes))), i))
+    }
+
+    fn parse_strikethrough(&self, index: usize, flags: ParagraphFlags) -> Option<ParseReturn> {
+        self.parse_recursively(
+            index,
+            |parser, i| parser.check_at(i, "~~"),
+            ParagraphParser::parse_strikethrough_extra_wrap,
+            flags.with_strikethrough(),
+        )
+    }
+
+    fn parse_highlight_extra_wrap(&self, nodes: Nodes, i: usize) -> Option<ParseReturn> {
+        Some(ParseReturn(Node::Highlight(Box::new(Node::Nodes(nodes))), i))
+    }
+
+    fn parse_highlight(&self, index: usize, flags: ParagraphFlags) -> Option<ParseReturn> {
+        self.parse_recursively(
+            index,
+            |parser, i| parser.check_at(i, "=="),
+            ParagraphParser::parse_highlight_extra_wrap,
+            flags.with_highlight(),
+        )
+    }
+
+    fn parse_comment(&self, index: usize, _flags: ParagraphFlags) -> Option<ParseReturn> {
+        let mut i = index;
+
+        // Without the closing marker it is just text
+        while !self.at_end(i) {
+            if let (true, new_i) = self.check_at(i, "%%") {
+                return Some(ParseReturn(Node::Comment(self.chars[index..i].iter().collect()), new_i));
+            }
+
+            i += 1;
+        }
+
+        None
+    }
+
+    fn parse_math(&self, index: usize, _flags: ParagraphFlags) -> Option<ParseReturn> {
+        // Like in Obsidian, `$` must touch the formula on both sides, so that prices stay text
+        if self.at(index).is_none_or(|c| c.is_whitespace() || c == '$') {
+            return None;
+        }
+
+        let mut i = index;
+
+        while !self.at_end(i) {
+            if self.at(i) == Some('$') {
+                if self.at(i - 1).is_some_and(|c| c.is_whitespace()) || self.at(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+
+                return Some(ParseReturn(Node::Math(self.chars[index..i].iter().collect()), i + 1));
+            }
+
+            i += 1;
+        }
+
+        None
+    }
+
+    fn parse_escaped(&self, index: usize, _flags: ParagraphFlags) -> Option<ParseReturn> {
+        Some(ParseReturn(Node::Escaped(self.at(index)?), index + 1))
+    }
+
     fn parse_tag(&self, index: usize, _flags: ParagraphFlags) -> Option<ParseReturn> {
         let mut current = String::new();
         let mut i = index;
@@ -252,13 +466,16 @@ impl ParagraphParser {
 
         while !self.at_end(i) {
             if let (true, new_i) = self.check_at(i, "]]") {
+                let (target, anchor) = split_anchor(&target);
+
                 return Some(ParseReturn(
                     Node::Link {
                         embed,
                         link: Link::with_target(
                             markdown::LinkKind::Internal,
                             markdown::LinkTarget::Zettel(target),
-                        ),
+                        )
+                        .with_anchor(anchor),
                     },
                     new_i,
                 ));
@@ -266,6 +483,8 @@ impl ParagraphParser {
                 // Call the parser recursively to parse the text after the '|'.
                 // As a terminal condition, we check for ']]'.
                 // The wrapper function takes care of the rest.
+                let (target, anchor) = split_anchor(&target);
+
                 return self.parse_recursively(
                     new_i,
                     |parser, i| parser.check_at(i, "]]"),
@@ -277,7 +496,8 @@ impl ParagraphParser {
                                     markdown::LinkKind::Internal,
                                     markdown::LinkTarget::Zettel(target.clone()),
                                     nodes,
-                                ),
+                                )
+                                .with_anchor(anchor.clone()),
                             },
                             i,
                         ))
@@ -346,6 +566,27 @@ impl ParagraphParser {
                     new_i,
                 ));
             }
+
+            // Everything else is a path inside of the vault, like `[text](folder/note.md#Heading)`
+            let closed = new_i > 0 && self.at(new_i - 1) == Some(')');
+            let path = url.trim().trim_start_matches('<').trim_end_matches('>');
+
+            if closed && !path.is_empty() {
+                let (path, anchor) = split_anchor(&percent_decode(path));
+
+                return Some(ParseReturn(
+                    Node::Link {
+                        embed,
+                        link: Link::with_title(
+                            markdown::LinkKind::Internal,
+                            markdown::LinkTarget::FreeForm(path),
+                            nodes,
+                        )
+                        .with_anchor(anchor),
+                    },
+                    new_i,
+                ));
+            }
         }
 
         None
@@ -384,6 +625,75 @@ impl ParagraphParser {
             }
         }
 
+        fn find_escaped(
+            parser: &ParagraphParser,
+            index: usize,
+            _flags: ParagraphFlags,
+        ) -> Option<LittleParser> {
+            match (parser.at(index), parser.at(index + 1)) {
+                (Some('\\'), Some(c)) if c.is_ascii_punctuation() => {
+                    Some(LittleParser::new(ParagraphParser::parse_escaped, index + 1))
+                }
+                _ => None,
+            }
+        }
+
+        fn find_strikethrough(
+            parser: &ParagraphParser,
+            index: usize,
+            flags: ParagraphFlags,
+        ) -> Option<LittleParser> {
+            if flags.strikethrough {
+                return None;
+            }
+
+            if let (true, new_i) = parser.check_at(index, "~~") {
+                Some(LittleParser::new(ParagraphParser::parse_strikethrough, new_i))
+            } else {
+                None
+            }
+        }
+
+        fn find_highlight(
+            parser: &ParagraphParser,
+            index: usize,
+            flags: ParagraphFlags,
+        ) -> Option<LittleParser> {
+            if flags.highlight {
+                return None;
+            }
+
+            if let (true, new_i) = parser.check_at(index, "==") {
+                Some(LittleParser::new(ParagraphParser::parse_highlight, new_i))
+            } else {
+                None
+            }
+        }
+
+        fn find_comment(
+            parser: &ParagraphParser,
+            index: usize,
+            _flags: ParagraphFlags,
+        ) -> Option<LittleParser> {
+            if let (true, new_i) = parser.check_at(index, "%%") {
+                Some(LittleParser::new(ParagraphParser::parse_comment, new_i))
+            } else {
+                None
+            }
+        }
+
+        fn find_math(
+            parser: &ParagraphParser,
+            index: usize,
+            _flags: ParagraphFlags,
+        ) -> Option<LittleParser> {
+            if let (true, new_i) = parser.check_at(index, "$") {
+                Some(LittleParser::new(ParagraphParser::parse_math, new_i))
+            } else {
+                None
+            }
+        }
+
         fn find_bold(
             parser: &ParagraphParser,
             index: usize,
@@ -483,8 +793,13 @@ impl ParagraphParser {
 
         let finders = [
             find_newline,
+            find_escaped,
+            find_comment,
             find_bold,
             find_italic,
+            find_strikethrough,
+            find_highlight,
+            find_math,
             find_tag,
             find_inline_code_block,
             find_link,
@@ -551,44 +866,56 @@ impl ParagraphParser {
     {
         let mut nodes = Vec::new();
         let mut current_string = String::new();
+        let mut text_start = i;
 
         while !self.at_end(i) {
             if let (true, new_i) = extra_end_condition(self, i) {
+                if !current_string.is_empty() {
+                    nodes.push(Spanned::new(Node::Text(current_string), self.span(text_start, i)));
+                    current_string = String::new();
+                }
                 i = new_i;
                 break;
             }
 
             if let Some((new_i, url)) = self.try_parse_url(self, i) {
-                i = new_i;
                 if !current_string.is_empty() {
-                    nodes.push(Node::Text(current_string));
+                    nodes.push(Spanned::new(Node::Text(current_string), self.span(text_start, i)));
                     current_string = String::new();
                 }
-                nodes.push(Node::Link {
-                    embed: false,
-                    link: Link::with_target(
-                        markdown::LinkKind::External,
-                        markdown::LinkTarget::Url(url),
-                    ),
-                });
+                nodes.push(Spanned::new(
+                    Node::Link {
+                        embed: false,
+                        link: Link::with_target(
+                            markdown::LinkKind::External,
+                            markdown::LinkTarget::Url(url),
+                        ),
+                    },
+                    self.span(i, new_i),
+                ));
+                i = new_i;
                 continue;
             }
 
             if let Some(ParseReturn(new_node, new_i)) = self.try_run_parsers(i, flags) {
                 if !current_string.is_empty() {
-                    nodes.push(Node::Text(current_string));
+                    nodes.push(Spanned::new(Node::Text(current_string), self.span(text_start, i)));
                     current_string = String::new();
                 }
-                nodes.push(new_node);
+                // The parsers start behind the opening marker, the span includes it
+                nodes.push(Spanned::new(new_node, self.span(i, new_i)));
                 i = new_i;
             } else {
+                if current_string.is_empty() {
+                    text_start = i;
+                }
                 current_string.push(self.at(i).unwrap());
                 i += 1;
             }
         }
 
         if !current_string.is_empty() {
-            nodes.push(Node::Text(current_string));
+            nodes.push(Spanned::new(Node::Text(current_string), self.span(text_start, i)));
         }
 
         extra_wrap(self, nodes, i)
@@ -619,29 +946,87 @@ impl ParagraphParser {
 struct CodeBlock {
     lang: Option<String>,
     lines: Vec<String>,
+    span: Span,
 }
 
 struct Callout {
     kind: Option<String>,
+    fold: Option<markdown::CalloutFold>,
+    title: markdown::Nodes,
+    lines: Vec<SourceLine>,
+    span: Span,
+}
+
+struct ListLine {
+    /// The indentation in spaces, tabs count as four.
+    indent: usize,
+    kind: markdown::ListKind,
+    item: markdown::ListItem,
+}
+
+struct Table {
+    table: markdown::Table,
+    /// The line after the header row only holds the alignments, it is skipped.
+    delimiter_seen: bool,
+    span: Span,
+}
+
+/// A `%%` comment whose lines may be separated by empty lines.
+struct Comment {
     lines: Vec<String>,
+    span: Span,
 }
 
 enum CurrentItem {
     CodeBlock(CodeBlock),
+    Comment(Comment),
     Callout(Callout),
+    List(Vec<ListLine>),
+    Table(Table),
+}
+
+/// The span of a list, from its first item to the end of its last nested item.
+fn list_span(list: &markdown::List) -> Span {
+    let first = list.items.first().map(|item| item.span).unwrap_or_default();
+    let end = match list.items.last() {
+        Some(item) => match item.children.last() {
+            Some(child) => list_span(child).end,
+            None => item.span.end,
+        },
+        None => first.end,
+    };
+
+    Span::new(first.start, end)
 }
 
 pub struct MarkdownParser {
-    lines: Vec<String>,
+    lines: Vec<SourceLine>,
 }
 
 impl MarkdownParser {
     pub fn for_string<S: ToString>(string: S) -> MarkdownParser {
-        let lines = string
-            .to_string()
-            .lines()
-            .map(|line| line.to_string())
-            .collect::<Vec<String>>();
+        MarkdownParser::for_string_at(string, Position::default())
+    }
+
+    /// Count the positions starting at `start`, e.g. because the text comes after the frontmatter.
+    pub fn for_string_at<S: ToString>(string: S, start: Position) -> MarkdownParser {
+        let string = string.to_string();
+        let mut lines = Vec::new();
+        let mut position = start;
+
+        for raw in string.split_inclusive('\n') {
+            let text = raw
+                .strip_suffix('\n')
+                .map(|text| text.strip_suffix('\r').unwrap_or(text))
+                .unwrap_or(raw);
+
+            lines.push(SourceLine {
+                text: text.to_string(),
+                start: position,
+            });
+            position = position.advanced_by(raw);
+        }
+
         MarkdownParser { lines }
     }
 
@@ -653,159 +1038,468 @@ impl MarkdownParser {
         }
     }
 
-    fn parse_heading(&self, line: &str) -> Option<markdown::Heading> {
-        match count_leading_chars(line, '#') {
+    fn parse_heading(&self, line: &SourceLine) -> Option<markdown::Heading> {
+        match count_leading_chars(&line.text, '#') {
             0 => None,
             level => {
-                let text: String = line.chars().skip(level).skip_while(|c| *c == ' ').collect();
+                let text = line.text[level..].trim_start_matches(' ');
                 Some(markdown::Heading(
                     level as u8,
-                    ParagraphParser::for_string(text).parse(),
+                    ParagraphParser::for_lines(&[line.suffix(text)]).parse(),
                 ))
             }
         }
     }
 
-    fn parse_bullet_point(&self, line: &str) -> Option<markdown::BulletPoint> {
-        // Check for multiple spaces followed by a bullet point character (either - or *)
-
-        let leading_spaces = count_leading_chars(line, ' ');
+    /// The first line of a callout, e.g. `[!tip]- A title`: the kind, the fold marker and the title.
+    fn parse_callout_header(
+        &self,
+        line: &SourceLine,
+    ) -> Option<(String, Option<markdown::CalloutFold>, markdown::Nodes)> {
+        let rest = line.text.strip_prefix("[!")?;
+        let kind_end = rest.find(']')?;
+        let kind = rest[..kind_end].trim().to_string();
+
+        if kind.is_empty() {
+            return None;
+        }
 
-        let first_char = line.chars().skip(leading_spaces).next();
+        let rest = &rest[kind_end + 1..];
 
-        if first_char == Some('-') || first_char == Some('*') {
-            let text: String = line
-                .chars()
-                .skip(leading_spaces + 1)
-                .skip_while(|c| *c == ' ')
-                .collect();
+        let (fold, rest) = match rest.chars().next() {
+            Some('+') => (Some(markdown::CalloutFold::Expanded), &rest[1..]),
+            Some('-') => (Some(markdown::CalloutFold::Collapsed), &rest[1..]),
+            _ => (None, rest),
+        };
 
-            // If the text starts with a task indicator (a [ at position 0 and a ] at position 2), parse it as a task
-            let (status, text) = split_task_string(&text);
+        let title = rest.trim();
 
-            Some(markdown::BulletPoint(
-                status,
-                ParagraphParser::for_string(text).parse(),
-            ))
+        let title = if title.is_empty() {
+            vec![]
         } else {
-            None
+            ParagraphParser::for_lines(&[line.suffix(rest.trim_start()).prefix(title.len())]).parse()
+        };
+
+        Some((kind, fold, title))
+    }
+
+    fn parse_list_item(&self, line: &SourceLine) -> Option<ListLine> {
+        // Tabs count as four spaces, everything after the indentation is the marker
+        let indent = line
+            .text
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+
+        let rest = line.text.trim_start_matches([' ', '\t']);
+
+        let (kind, marker_len) = match rest.chars().next() {
+            Some('-') | Some('*') => (markdown::ListKind::Unordered, 1),
+            Some(c) if c.is_ascii_digit() => {
+                let digits = count_leading_chars_matching(rest, |c| c.is_ascii_digit());
+
+                match rest[digits..].chars().next() {
+                    Some('.') | Some(')') if digits <= 9 => {
+                        (markdown::ListKind::Ordered(rest[..digits].parse().ok()?), digits + 1)
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        // The marker has to be followed by a space, otherwise `*emphasis*` would be a list item
+        let text = &rest[marker_len..];
+
+        if !text.is_empty() && !text.starts_with(' ') {
+            return None;
         }
+
+        let text = text.trim_start_matches(' ');
+
+        // If the text starts with a task indicator (a [ at position 0 and a ] at position 2), parse it as a task
+        let (task, text) = split_task_string(text);
+
+        Some(ListLine {
+            indent,
+            kind,
+            item: markdown::ListItem {
+                task,
+                nodes: ParagraphParser::for_lines(&[line.suffix(&text)]).parse(),
+                children: Vec::new(),
+                span: Span::new(line.start, line.end()),
+            },
+        })
     }
 
-    fn parse_paragraph(&self, text: &str) -> markdown::Nodes {
-        ParagraphParser::for_string(text).parse()
+    /// Turn consecutive list items into lists, using the indentation to find out which
+    /// items are nested below which.
+    fn build_lists(
+        &self,
+        lines: &mut std::iter::Peekable<std::vec::IntoIter<ListLine>>,
+        parent_indent: Option<usize>,
+        depth: usize,
+    ) -> Vec<markdown::List> {
+        let mut lists: Vec<markdown::List> = Vec::new();
+
+        while let Some(line) =
+            lines.next_if(|line| parent_indent.is_none_or(|indent| line.indent > indent))
+        {
+            let mut item = line.item;
+            item.children = self.build_lists(lines, Some(line.indent), depth + 1);
+
+            match lists.last_mut() {
+                Some(list) if list.kind.continues(&line.kind) => list.items.push(item),
+                _ => lists.push(markdown::List {
+                    kind: line.kind,
+                    depth,
+                    items: vec![item],
+                }),
+            }
+        }
+
+        lists
     }
 
-    fn try_parse_line(&self, line: &str) -> Option<markdown::Block> {
-        if let Some(thematic_break) = self.parse_thematic_break(line) {
-            Some(thematic_break)
-        } else if let Some(heading) = self.parse_heading(line) {
-            Some(markdown::Block::Heading(heading))
-        } else if let Some(bullet_point) = self.parse_bullet_point(line) {
-            Some(markdown::Block::BulletPoint(bullet_point))
-        } else {
-            None
+    /// Split a table row into its cells. Pipes can be escaped with `\|`, and the ones
+    /// inside of wiki links (`[[target|caption]]`) don't count either.
+    fn split_table_row(&self, line: &SourceLine) -> Vec<ParagraphParser> {
+        let line = line.suffix(line.text.trim_start());
+        let line = match line.text.strip_prefix('|') {
+            Some(rest) => line.suffix(rest),
+            None => line,
+        };
+
+        // The chars of the current cell, each with its position in the source
+        let mut cells = Vec::new();
+        let mut current: Vec<(char, Position)> = Vec::new();
+        let mut chars = line.text.char_indices().peekable();
+        let mut in_link = false;
+
+        let position_at = |index: usize| line.start.advanced_by(&line.text[..index]);
+
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\\' if chars.peek().map(|(_, c)| *c) == Some('|') => {
+                    current.push(('|', position_at(index)));
+                    chars.next();
+                }
+                '[' if chars.peek().map(|(_, c)| *c) == Some('[') => {
+                    in_link = true;
+                    current.push((c, position_at(index)));
+                }
+                ']' if chars.peek().map(|(_, c)| *c) == Some(']') => {
+                    in_link = false;
+                    current.push((c, position_at(index)));
+                }
+                '|' if !in_link => cells.push((std::mem::take(&mut current), position_at(index))),
+                _ => current.push((c, position_at(index))),
+            }
+        }
+
+        // A trailing pipe closes the row instead of starting another cell
+        if current.iter().any(|(c, _)| !c.is_whitespace()) {
+            cells.push((current, line.end()));
+        }
+
+        cells
+            .into_iter()
+            .map(|(cell, end)| {
+                let first = cell.iter().position(|(c, _)| !c.is_whitespace());
+                let last = cell.iter().rposition(|(c, _)| !c.is_whitespace());
+
+                let (chars, mut positions): (Vec<char>, Vec<Position>) = match (first, last) {
+                    (Some(first), Some(last)) => cell[first..=last].iter().cloned().unzip(),
+                    _ => (Vec::new(), Vec::new()),
+                };
+
+                // The position after the last char is where the trimmed whitespace or the pipe starts
+                let after = last
+                    .and_then(|last| cell.get(last + 1))
+                    .map(|(_, position)| *position)
+                    .unwrap_or(end);
+                positions.push(after);
+
+                ParagraphParser { chars, positions }
+            })
+            .collect()
+    }
+
+    fn parse_table_alignments(&self, line: &SourceLine) -> Option<Vec<Option<markdown::Alignment>>> {
+        if !line.text.contains('-') {
+            return None;
         }
+
+        self.split_table_row(line)
+            .iter()
+            .map(|cell| {
+                let cell = cell.text();
+                let left = cell.starts_with(':');
+                let right = cell.ends_with(':');
+                let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+
+                if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                    return None;
+                }
+
+                Some(match (left, right) {
+                    (true, true) => Some(markdown::Alignment::Center),
+                    (true, false) => Some(markdown::Alignment::Left),
+                    (false, true) => Some(markdown::Alignment::Right),
+                    (false, false) => None,
+                })
+            })
+            .collect()
     }
 
-    pub fn pre_parse_lines(&self, lines: &Vec<String>) -> Vec<PreParsed> {
+    fn parse_table_row(&self, line: &SourceLine, columns: usize) -> Vec<markdown::Nodes> {
+        let mut cells = self
+            .split_table_row(line)
+            .iter()
+            .map(ParagraphParser::parse)
+            .collect::<Vec<_>>();
+
+        // Like in GFM, missing cells are empty and excess cells are ignored
+        cells.resize_with(columns, Vec::new);
+        cells
+    }
+
+    /// A table starts with a header row, followed by a delimiter row with as many columns.
+    fn parse_table_start(&self, line: &SourceLine, next_line: Option<&SourceLine>) -> Option<markdown::Table> {
+        if !line.text.contains('|') {
+            return None;
+        }
+
+        let alignments = self.parse_table_alignments(next_line?)?;
+
+        if alignments.len() != self.split_table_row(line).len() {
+            return None;
+        }
+
+        Some(markdown::Table {
+            header: self.parse_table_row(line, alignments.len()),
+            alignments,
+            rows: Vec::new(),
+        })
+    }
+
+    fn parse_paragraph(&self, lines: &[SourceLine]) -> markdown::Nodes {
+        ParagraphParser::for_lines(lines).parse()
+    }
+
+    fn try_parse_line(&self, line: &SourceLine) -> Option<markdown::Block> {
+        self.parse_thematic_break(&line.text)
+            .or_else(|| self.parse_heading(line).map(markdown::Block::Heading))
+    }
+
+    /// Turn a finished item into blocks.
+    fn push_item(&self, item: CurrentItem, pre_parsed: &mut Vec<PreParsed>) {
+        let block = match item {
+            CurrentItem::CodeBlock(cb) => {
+                Spanned::new(markdown::Block::Code(cb.lang, cb.lines.join("\n")), cb.span)
+            }
+            CurrentItem::Callout(callout) => Spanned::new(
+                markdown::Block::Callout(markdown::Callout {
+                    kind: callout.kind,
+                    fold: callout.fold,
+                    title: callout.title,
+                    blocks: self.parse_lines(&callout.lines),
+                }),
+                callout.span,
+            ),
+            CurrentItem::Table(table) => Spanned::new(markdown::Block::Table(table.table), table.span),
+            CurrentItem::Comment(comment) => {
+                // The closing line may end with whitespace after the `%%`
+                let lines = comment.lines.join("\n");
+                let text = lines.trim_end();
+                let text = text.strip_prefix("%%").unwrap_or(text);
+                let text = text.strip_suffix("%%").unwrap_or(text);
+
+                Spanned::new(
+                    markdown::Block::Nodes(vec![Spanned::new(markdown::Node::Comment(text.to_string()), comment.span)]),
+                    comment.span,
+                )
+            }
+            CurrentItem::List(list_lines) => {
+                for list in self.build_lists(&mut list_lines.into_iter().peekable(), None, 0) {
+                    let span = list_span(&list);
+                    pre_parsed.push(PreParsed::Parsed(Box::new(Spanned::new(
+                        markdown::Block::List(list),
+                        span,
+                    ))));
+                }
+                return;
+            }
+        };
+
+        pre_parsed.push(PreParsed::Parsed(Box::new(block)));
+    }
+
+    pub fn pre_parse_lines(&self, lines: &[SourceLine]) -> Vec<PreParsed> {
         let mut pre_parsed = Vec::new();
         let mut current_block = Vec::new();
         let mut current_item = None;
 
-        for line in lines {
+        for (index, line) in lines.iter().enumerate() {
+            let text = line.text.as_str();
+            let line_span = Span::new(line.start, line.end());
+
             if let Some(CurrentItem::CodeBlock(cb)) = &mut current_item {
-                if line == "```" {
-                    pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::Code(
-                        cb.lang.clone(),
-                        cb.lines.join("\n"),
-                    ))));
-                    current_item = None;
+                cb.span.end = line_span.end;
+
+                if text == "```" {
+                    if let Some(item) = current_item.take() {
+                        self.push_item(item, &mut pre_parsed);
+                    }
                 } else {
-                    cb.lines.push(line.clone());
+                    cb.lines.push(text.to_string());
                 }
-            } else if let Some(CurrentItem::Callout(callout)) = &mut current_item {
-                if line.starts_with(">") {
-                    callout.lines.push(line.chars().skip(2).collect());
-                } else {
-                    pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::Callout(
-                        callout.kind.clone(),
-                        self.parse_lines(&callout.lines),
-                    ))));
-                    current_item = None;
-                    current_block.push(line.clone());
+                continue;
+            }
+
+            if let Some(CurrentItem::Comment(comment)) = &mut current_item {
+                comment.lines.push(text.to_string());
+                comment.span.end = line_span.end;
+
+                if text.trim_end().ends_with("%%") {
+                    if let Some(item) = current_item.take() {
+                        self.push_item(item, &mut pre_parsed);
+                    }
+                }
+                continue;
+            }
+
+            if let Some(CurrentItem::Callout(callout)) = &mut current_item {
+                if text.starts_with(">") {
+                    callout.lines.push(line.skip_chars(2));
+                    callout.span.end = line_span.end;
+                    continue;
+                }
+
+                // The callout ends here, the line itself is handled like any other
+                if let Some(item) = current_item.take() {
+                    self.push_item(item, &mut pre_parsed);
+                }
+            }
+
+            if let Some(CurrentItem::List(list_lines)) = &mut current_item {
+                // Empty lines between the items don't end the list
+                if let Some(list_line) = self.parse_list_item(line) {
+                    list_lines.push(list_line);
+                    continue;
+                } else if text.is_empty() {
+                    continue;
+                }
+
+                if let Some(item) = current_item.take() {
+                    self.push_item(item, &mut pre_parsed);
+                }
+            }
+
+            if let Some(CurrentItem::Table(table)) = &mut current_item {
+                if !table.delimiter_seen {
+                    table.delimiter_seen = true;
+                    table.span.end = line_span.end;
+                    continue;
+                } else if text.contains('|') {
+                    let columns = table.table.alignments.len();
+                    table.table.rows.push(self.parse_table_row(line, columns));
+                    table.span.end = line_span.end;
+                    continue;
                 }
-            } else if line.starts_with("```") {
+
+                if let Some(item) = current_item.take() {
+                    self.push_item(item, &mut pre_parsed);
+                }
+            }
+
+            let list_line = self.parse_list_item(line).filter(|list_line| {
+                // Like in CommonMark, only a list starting at 1 may interrupt a paragraph
+                current_block.is_empty() || !matches!(list_line.kind, markdown::ListKind::Ordered(start) if start != 1)
+            });
+
+            if let Some(table) = self.parse_table_start(line, lines.get(index + 1)) {
+                if !current_block.is_empty() {
+                    pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
+                    current_block = Vec::new();
+                }
+                current_item = Some(CurrentItem::Table(Table {
+                    table,
+                    delimiter_seen: false,
+                    span: line_span,
+                }));
+            } else if text.starts_with("%%")
+                && !text[2..].contains("%%")
+                && lines[index + 1..].iter().any(|line| line.text.trim_end().ends_with("%%"))
+            {
+                // Unlike the inline comments, these may hide several paragraphs
+                if !current_block.is_empty() {
+                    pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
+                    current_block = Vec::new();
+                }
+                current_item = Some(CurrentItem::Comment(Comment {
+                    lines: vec![text.to_string()],
+                    span: line_span,
+                }));
+            } else if text.starts_with("```") {
                 if !current_block.is_empty() {
                     pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
                     current_block = Vec::new();
                 }
                 current_item = Some(CurrentItem::CodeBlock(CodeBlock {
-                    lang: if line.len() > 3 {
-                        Some(line.chars().skip(3).collect())
+                    lang: if text.len() > 3 {
+                        Some(text.chars().skip(3).collect())
                     } else {
                         None
                     },
                     lines: Vec::new(),
+                    span: line_span,
                 }));
-            } else if line.starts_with(">") {
+            } else if text.starts_with(">") {
                 if !current_block.is_empty() {
                     pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
                     current_block = Vec::new();
                 }
 
-                let line: String = line.chars().skip(2).collect();
-
-                // If the line is "[!kind] text" (ignore leading whitespaces), parse the kind and start a new callout
-
-                let (lines, kind) = if line.starts_with("[!") {
-                    let kind_end = line.find(']').unwrap();
-                    let kind = line.chars().skip(2).take(kind_end - 2).collect();
-                    // let line: String = line.chars().skip(kind_end + 2).collect();
-
-                    (vec![], Some(kind))
-                } else {
-                    (vec![line], None)
-                };
+                let line = line.skip_chars(2);
 
-                current_item = Some(CurrentItem::Callout(Callout { kind, lines }));
-            } else if line.starts_with(" - ") || line.starts_with(" * ") {
+                // If the line is "[!kind]" with an optional fold marker and title, a callout starts here
+                current_item = Some(CurrentItem::Callout(match self.parse_callout_header(&line) {
+                    Some((kind, fold, title)) => Callout {
+                        kind: Some(kind),
+                        fold,
+                        title,
+                        lines: vec![],
+                        span: line_span,
+                    },
+                    None => Callout {
+                        kind: None,
+                        fold: None,
+                        title: vec![],
+                        lines: vec![line],
+                        span: line_span,
+                    },
+                }));
+            } else if let Some(list_line) = list_line {
                 if !current_block.is_empty() {
                     pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
                     current_block = Vec::new();
                 }
-                pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::BulletPoint(
-                    self.parse_bullet_point(line).unwrap(),
-                ))));
-            } else if line.is_empty() {
+                current_item = Some(CurrentItem::List(vec![list_line]));
+            } else if text.is_empty() {
+                // Technically, we have a new paragraph here. So we should push the current block
+                // and start a new one.
                 if !current_block.is_empty() {
                     pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
                     current_block = Vec::new();
                 }
-                // Technically, we have a new paragraph here. So we should push the current block
-                // and start a new one.
-
-                if let Some(CurrentItem::CodeBlock(cb)) = current_item {
-                    pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::Code(
-                        cb.lang,
-                        cb.lines.join("\n"),
-                    ))));
-                    current_item = None;
-                } else if let Some(CurrentItem::Callout(callout)) = current_item {
-                    pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::Callout(
-                        callout.kind,
-                        self.parse_lines(&callout.lines),
-                    ))));
-                    current_item = None;
-                } else if let Some(block) = self.try_parse_line(line) {
-                    pre_parsed.push(PreParsed::Parsed(Box::new(block)));
-                }
             } else if let Some(block) = self.try_parse_line(line) {
                 if !current_block.is_empty() {
                     pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
                     current_block = Vec::new();
                 }
-                pre_parsed.push(PreParsed::Parsed(Box::new(block)));
+                pre_parsed.push(PreParsed::Parsed(Box::new(Spanned::new(block, line_span))));
             } else {
                 current_block.push(line.clone());
             }
@@ -813,28 +1507,24 @@ impl MarkdownParser {
 
         if !current_block.is_empty() {
             pre_parsed.push(PreParsed::Unparsed(Box::new(current_block)));
-        } else if let Some(CurrentItem::CodeBlock(cb)) = current_item {
-            pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::Code(
-                cb.lang,
-                cb.lines.join("\n"),
-            ))));
-        } else if let Some(CurrentItem::Callout(callout)) = current_item {
-            pre_parsed.push(PreParsed::Parsed(Box::new(markdown::Block::Callout(
-                callout.kind,
-                self.parse_lines(&callout.lines),
-            ))));
+        } else if let Some(item) = current_item {
+            self.push_item(item, &mut pre_parsed);
         }
 
         pre_parsed
     }
 
-    fn parse_lines(&self, lines: &Vec<String>) -> markdown::Blocks {
+    fn parse_lines(&self, lines: &[SourceLine]) -> markdown::Blocks {
         self.pre_parse_lines(lines)
             .into_iter()
             .map(|pre_parsed| match pre_parsed {
                 PreParsed::Parsed(block) => *block,
                 PreParsed::Unparsed(lines) => {
-                    markdown::Block::Nodes(self.parse_paragraph(&lines.join("\n")))
+                    let span = Span::new(
+                        lines.first().map(|line| line.start).unwrap_or_default(),
+                        lines.last().map(SourceLine::end).unwrap_or_default(),
+                    );
+                    Spanned::new(markdown::Block::Nodes(self.parse_paragraph(&lines)), span)
                 }
             })
             .collect()
@@ -851,6 +1541,11 @@ pub fn parse_document(text: String) -> Result<markdown::Document, ParseError> {
     MarkdownParser::for_string(text).parse()
 }
 
+/// Like `parse_document`, for text that starts at `start` of its file.
+pub fn parse_document_at(text: String, start: Position) -> Result<markdown::Document, ParseError> {
+    MarkdownParser::for_string_at(text, start).parse()
+}
+
 ///
 /// Parse a text snippet into a list of nodes.
 ///
@@ -858,3 +1553,234 @@ pub fn parse_text_snippet<S: ToString>(content: S) -> Result<super::Nodes, ()> {
     let parser = ParagraphParser::for_string(content);
     Ok(parser.parse())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::core::entity::zettel::document::block::{list::Kind, table::Alignment, Block};
+    use crate::formats::markdown::{self, testing::parse_blocks};
+
+    #[test]
+    fn lists_are_nested() {
+        let blocks = parse_blocks(" - a\n   - b\n     1. c\n - d\n\n* e\n\n5) f\n6) g\n");
+
+        assert_eq!(blocks.len(), 2);
+
+        let Block::List(list) = &blocks[0].value else { panic!("{:?}", blocks[0]) };
+        assert_eq!(list.kind, Kind::Unordered);
+        assert_eq!(list.items.len(), 3);
+        assert_eq!(list.items[0].children[0].depth, 1);
+        assert_eq!(list.items[0].children[0].items[0].children[0].kind, Kind::Ordered { start: 1 });
+        assert_eq!(list.items[0].children[0].items[0].children[0].depth, 2);
+
+        let Block::List(list) = &blocks[1].value else { panic!("{:?}", blocks[1]) };
+        assert_eq!(list.kind, Kind::Ordered { start: 5 });
+        assert_eq!(list.items.len(), 2);
+    }
+
+    #[test]
+    fn emphasis_and_numbers_are_not_lists() {
+        let blocks = parse_blocks("*emphasis* at the start\n\nSome text\n2024. was a year\n");
+
+        assert!(blocks.iter().all(|block| matches!(block.value, Block::Paragraph(_))), "{:?}", blocks);
+    }
+
+    #[test]
+    fn tables_are_parsed() {
+        let blocks = parse_blocks("a | b\n:-- | --:\n[[target]] | #tag | extra\nonly one\n");
+
+        let Block::Table(table) = &blocks[0].value else { panic!("{:?}", blocks) };
+        assert_eq!(table.alignments, vec![Some(Alignment::Left), Some(Alignment::Right)]);
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.rows[0].len(), 2);
+
+        // The line without a pipe ends the table
+        assert!(matches!(blocks[1].value, Block::Paragraph(_)));
+    }
+
+    #[test]
+    fn pipes_without_delimiter_row_are_text() {
+        let blocks = parse_blocks("a | b\nc | d\n\n| a | b |\n| --- |\n");
+
+        assert!(blocks.iter().all(|block| matches!(block.value, Block::Paragraph(_))), "{:?}", blocks);
+    }
+
+    #[test]
+    fn spans_point_into_the_source() {
+        use crate::core::entity::zettel::document::node::Node;
+
+        let source = "---\ntitle: Spans\n---\n# Head\r\n\nSome **bold** text\nsecond line\n\n| a | b |\n|---|---|\n| [[x]] | \\| y |\n\n```\ncode\n```\n";
+        let blocks = parse_blocks(source);
+        let text_of = |span: crate::util::span::Span| &source[span.bytes()];
+
+        let texts = blocks.iter().map(|block| text_of(block.span)).collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            vec![
+                "# Head",
+                "Some **bold** text\nsecond line",
+                "| a | b |\n|---|---|\n| [[x]] | \\| y |",
+                "```\ncode\n```",
+            ]
+        );
+        assert_eq!(blocks[1].span.lines(), 5..=6);
+
+        let Block::Paragraph(paragraph) = &blocks[1].value else { panic!("{:?}", blocks) };
+        assert!(matches!(paragraph.nodes[1].value, Node::Styled(..)));
+        assert_eq!(text_of(paragraph.nodes[1].span), "**bold**");
+        assert_eq!(text_of(paragraph.nodes[2].span), " text\nsecond line");
+
+        let Block::Table(table) = &blocks[2].value else { panic!("{:?}", blocks) };
+        assert_eq!(text_of(table.rows[0][0][0].span), "[[x]]");
+        assert_eq!(text_of(table.rows[0][1][0].span), "\\| y");
+    }
+
+    #[test]
+    fn links_with_anchors() {
+        use crate::core::entity::{
+            link::reference::{Anchor, Reference},
+            zettel::document::{block::Block, node::Node},
+            Id,
+        };
+
+        let blocks = parse_blocks("See [[c#Hello, world!]] and ![[c#^abc-1|the block]]. ^para\n");
+
+        let Block::Paragraph(paragraph) = &blocks[0].value else { panic!("{:?}", blocks) };
+        assert_eq!(paragraph.block_id.as_deref(), Some("para"));
+
+        let references = paragraph
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.value {
+                Node::Reference(reference) => Some(reference),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(references[0].target, Reference::Entity(Id::from_string("c").unwrap()));
+        assert_eq!(references[0].anchor, Some(Anchor::Heading("Hello, world!".to_string())));
+        assert_eq!(references[0].anchor.as_ref().unwrap().html_id(), "hello-world");
+        assert_eq!(references[1].anchor, Some(Anchor::Block("abc-1".to_string())));
+        assert!(references[1].embed);
+
+        // An empty anchor is dropped, only the first `#` separates the anchor
+        assert!(matches!(super::split_anchor("c#"), (note, None) if note == "c"));
+        assert!(matches!(
+            super::split_anchor("c#A#B"),
+            (note, Some(markdown::Anchor::Heading(heading))) if note == "c" && heading == "A#B"
+        ));
+    }
+
+    #[test]
+    fn relative_markdown_links_are_internal() {
+        use crate::core::entity::{
+            link::reference::{Anchor, Reference},
+            zettel::document::{block::Block, node::Node},
+            Id,
+        };
+
+        let blocks = parse_blocks("See [the note](folder/my%20note.md#Part) and [a] b.\n");
+
+        let Block::Paragraph(paragraph) = &blocks[0].value else { panic!("{:?}", blocks) };
+
+        let references = paragraph
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.value {
+                Node::Reference(reference) => Some(reference),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].target, Reference::Entity(Id::from_string("folder/my note.md").unwrap()));
+        assert_eq!(references[0].anchor, Some(Anchor::Heading("Part".to_string())));
+        assert!(matches!(&paragraph.nodes.last().unwrap().value, Node::Text(text) if text.ends_with("and [a] b.")));
+    }
+
+    #[test]
+    fn callouts_with_titles_and_folding() {
+        use crate::core::entity::zettel::document::{
+            block::callout::{Fold, Kind},
+            conversions::to_markdown::ToMarkdown,
+        };
+
+        let blocks = parse_blocks("> [!FAQ]- Why?\n> Because.\n\n> [!error]\n> Broken\n\n> [!Custom_Thing]+\n\n> [!unclosed\n");
+
+        let Block::Callout(callout) = &blocks[0].value else { panic!("{:?}", blocks) };
+        assert_eq!(callout.kind, Kind::Question);
+        assert_eq!(callout.fold, Some(Fold::Collapsed));
+        assert_eq!(callout.title.to_markdown(), "Why?");
+        assert_eq!(callout.blocks.to_markdown(), "Because.");
+
+        let Block::Callout(callout) = &blocks[1].value else { panic!("{:?}", blocks) };
+        assert_eq!(callout.kind, Kind::Danger);
+        assert_eq!(callout.fold, None);
+        assert!(callout.title.is_empty());
+
+        let Block::Callout(callout) = &blocks[2].value else { panic!("{:?}", blocks) };
+        assert_eq!(callout.kind, Kind::Custom("custom_thing".to_string()));
+        assert_eq!(callout.fold, Some(Fold::Expanded));
+
+        // Without the closing bracket it is a plain quote
+        let Block::Callout(callout) = &blocks[3].value else { panic!("{:?}", blocks) };
+        assert_eq!(callout.kind, Kind::Basic);
+    }
+
+    #[test]
+    fn extended_inline_syntax() {
+        use crate::core::entity::zettel::document::node::{Node, Style};
+        use crate::formats::markdown::testing::{paragraph_nodes, styled, text, without_positions};
+
+        assert_eq!(
+            without_positions(&paragraph_nodes("a ~~gone **bold**~~ ==*marked*== b\n")),
+            without_positions(&vec![
+                text("a "),
+                styled(Style::Strikethrough, vec![text("gone "), styled(Style::Bold, vec![text("bold")])]),
+                text(" "),
+                styled(Style::Highlight, vec![styled(Style::Italic, vec![text("marked")])]),
+                text(" b"),
+            ])
+        );
+
+        assert_eq!(
+            paragraph_nodes("$e^{i\\pi}$ costs $5 and $10 %%hidden [[link]]%% \\*not italic\\*\n"),
+            vec![
+                Node::Math("e^{i\\pi}".to_string()),
+                text(" costs $5 and $10 "),
+                Node::Comment("hidden [[link]]".to_string()),
+                text(" "),
+                Node::Escaped('*'),
+                text("not italic"),
+                Node::Escaped('*'),
+            ]
+        );
+
+        // Unclosed comments and a lone backslash stay text
+        assert_eq!(paragraph_nodes("50%% or a \\ b\n"), vec![text("50%% or a \\ b")]);
+
+        let blocks = parse_blocks("**==[[c|the *c*]]==** %%note%%\n");
+        assert_eq!(blocks[0].to_plain_text(), "the c ");
+    }
+
+    #[test]
+    fn comments_span_paragraphs() {
+        use crate::core::entity::zettel::document::node::Node;
+        use crate::formats::markdown::testing::{paragraph_nodes, text};
+
+        let blocks = parse_blocks("Before\n%%\nhidden\n\n- [ ] not a task\n%%\n\nAfter\n");
+        assert_eq!(blocks.len(), 3, "{:?}", blocks);
+
+        let Block::Paragraph(paragraph) = &blocks[1].value else { panic!("{:?}", blocks) };
+        assert_eq!(paragraph.nodes[0].value, Node::Comment("\nhidden\n\n- [ ] not a task\n".to_string()));
+        assert_eq!(blocks[1].span.lines(), 1..=5);
+
+        // Whitespace after the closing `%%` isn't part of the comment
+        for closing in ["%% ", "%%\t\u{3000}"] {
+            let nodes = paragraph_nodes(&format!("%%\nhidden\n\nmore\n{}\n", closing));
+            assert_eq!(nodes, vec![Node::Comment("\nhidden\n\nmore\n".to_string())]);
+        }
+
+        // Without a closing line, the `%%` is text
+        assert_eq!(paragraph_nodes("%%\n\nMore\n"), vec![text("%%")]);
+    }
+}
@@ -0,0 +1,67 @@
+//! Fixtures for the tests of the parser and of the documents built from its output.
+
+use crate::core::{
+    entity::zettel::{
+        self,
+        document::{
+            self,
+            block::Block,
+            node::{Node, Style},
+        },
+    },
+    vault::resource,
+};
+use crate::util::span::Spanned;
+
+/// Parse a note the way it is read from a vault, frontmatter included.
+pub fn parse_zettel(source: &str) -> zettel::Zettel {
+    let content = resource::file::FileContent::new(
+        resource::Type::Zettel(resource::types::ZettelType::Obsidian),
+        None,
+        source.as_bytes().to_vec(),
+    );
+
+    let document = super::parse_obsidian_markdown(content).unwrap();
+    zettel::Zettel::from_obsidian_markdown(document).unwrap()
+}
+
+/// The blocks of the body of a note.
+pub fn parse_blocks(source: &str) -> document::Blocks {
+    parse_zettel(source).body_as_document().unwrap().blocks().clone()
+}
+
+/// The nodes of the first block of a note, which has to be a paragraph.
+pub fn paragraph_nodes(source: &str) -> Vec<Node> {
+    let blocks = parse_blocks(source);
+
+    let Block::Paragraph(paragraph) = &blocks[0].value else { panic!("{:?}", blocks) };
+    paragraph.nodes.iter().map(|node| node.value.clone()).collect()
+}
+
+pub fn text(text: &str) -> Node {
+    Node::Text(text.to_string())
+}
+
+/// Styled nodes the way the parser groups them, compare them `without_positions`.
+pub fn styled(style: Style, nodes: Vec<Node>) -> Node {
+    let nodes = nodes.into_iter().map(|node| Spanned::new(node, Default::default())).collect();
+    Node::Styled(style, Box::new(Node::Grouped(nodes)))
+}
+
+/// The value without the positions in the source, for comparing structures whose positions don't matter.
+pub fn without_positions<T: serde::Serialize>(value: &T) -> serde_json::Value {
+    fn strip(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(object) => {
+                object.remove("span");
+                object.values_mut().for_each(strip);
+            }
+            serde_json::Value::Array(array) => array.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+
+    let mut value = serde_json::to_value(value).unwrap();
+    strip(&mut value);
+    value
+}
@@ -0,0 +1,90 @@
+/*
+ * Splitting of mbox archives into single RFC 822 messages.
+ *
+ * Every message starts with a line beginning with "From " (the "From_" line),
+ * which is not part of the message itself. Lines in the body that would be
+ * mistaken for such a line are escaped with ">", which we undo here (mboxrd).
+ */
+
+fn is_from_line(line: &[u8]) -> bool {
+    line.starts_with(b"From ")
+}
+
+/// Undo the mboxrd quoting, i.e. turn ">From " into "From " and ">>From " into ">From ".
+fn unescape_line(line: &[u8]) -> &[u8] {
+    let quotes = line.iter().take_while(|c| **c == b'>').count();
+
+    if quotes > 0 && line[quotes..].starts_with(b"From ") {
+        &line[1..]
+    } else {
+        line
+    }
+}
+
+pub fn split_mbox(content: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+
+    for line in content.split_inclusive(|c| *c == b'\n') {
+        if is_from_line(line) {
+            messages.extend(current.take());
+            current = Some(Vec::new());
+            continue;
+        }
+
+        if let Some(message) = current.as_mut() {
+            message.extend_from_slice(unescape_line(line));
+        }
+    }
+
+    messages.extend(current);
+
+    // The blank line separating two messages belongs to the archive, not to the message
+    for message in messages.iter_mut() {
+        if message.ends_with(b"\r\n\r\n") {
+            message.truncate(message.len() - 2);
+        } else if message.ends_with(b"\n\n") {
+            message.truncate(message.len() - 1);
+        }
+    }
+
+    messages
+}
+
+/// Whether the content looks like an mbox archive rather than a single message.
+pub fn looks_like_mbox(content: &[u8]) -> bool {
+    is_from_line(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{looks_like_mbox, split_mbox};
+
+    #[test]
+    fn messages_are_split_at_from_lines() {
+        let archive = b"From a@b Mon Jan 1 00:00:00 2024\nSubject: One\n\nFirst\n\nFrom c@d Tue Jan 2 00:00:00 2024\r\nSubject: Two\r\n\r\nSecond\r\n\r\n";
+
+        assert!(looks_like_mbox(archive));
+        assert_eq!(
+            split_mbox(archive),
+            vec![
+                b"Subject: One\n\nFirst\n".to_vec(),
+                b"Subject: Two\r\n\r\nSecond\r\n".to_vec(),
+            ]
+        );
+
+        // The From_ line has to start the line, and a single message isn't an archive
+        assert!(!looks_like_mbox(b"Subject: x\n\nFrom here\n"));
+        assert!(split_mbox(b"Subject: x\n").is_empty());
+    }
+
+    #[test]
+    fn quoted_from_lines_are_unescaped() {
+        let archive = b"From a\n\n>From the start\n>>From quoted\n> From a quote\n>Fromage\n";
+
+        assert_eq!(
+            split_mbox(archive),
+            vec![b"\nFrom the start\n>From quoted\n> From a quote\n>Fromage\n".to_vec()]
+        );
+    }
+}
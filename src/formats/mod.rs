@@ -1,2 +1,4 @@
 
+pub mod email;
 pub mod markdown;
+pub mod mbox;
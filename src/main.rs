@@ -1,50 +1,40 @@
 
+pub mod cli;
 pub mod core;
 pub mod formats;
+pub mod search;
 pub mod semantic;
 pub mod util;
 pub mod web;
 
 
-fn suggest_vault_path() -> Option<std::path::PathBuf> {
-    // Suggest a vault path by looking at the environment variable ATHENA_VAULT_PATH.
-    // If that does not exist, try to find ~/Vaults/Obsidian
-    // If that does not exist, refer to the default path ./example
-    // If none of these exist, return None
-    // Use functional style
-
-    fn try_vault_path<T: Into<std::path::PathBuf>>(path: T) -> Option<std::path::PathBuf> {
-        let path = path.into();
-        if path.exists() {
-            Some(path)
-        } else {
-            None
-        }
-    }
-
-    let home_dir = std::env::var("HOME").ok();
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
 
-    let vault_path = std::env::var("ATHENA_VAULT_PATH")
-        .ok()
-        .and_then(try_vault_path)
-        .or_else(|| home_dir.and_then(|home_dir| try_vault_path(format!("{}/Vaults/Obsidian", home_dir))))
-        .or_else(|| try_vault_path("./example"));
-    
-    vault_path
-}
+    let cli = cli::Cli::parse();
 
+    let config = core::config()
+        .load_default_file()
+        .unwrap_or_else(|_| std::process::exit(1))
+        .build();
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
+    let bind_address = cli
+        .bind_address()
+        .map(String::from)
+        .unwrap_or_else(|| config.bind_address());
 
-    let vault_path = suggest_vault_path().unwrap_or_else(|| {
-        panic!("No vault path found. Please set the ATHENA_VAULT_PATH environment variable or create a vault at ~/Vaults/Obsidian or ./example");
+    let vault = core::vault::Vault::open(config).unwrap_or_else(|_| {
+        eprintln!("Unable to open the vault. Please configure your volumes in ~/.athena/config.toml, set the ATHENA_VAULT_PATH environment variable or create a vault at ~/Vaults/Obsidian or ./example");
+        std::process::exit(1);
     });
 
-    let vault = core::config()
-        .vault_path(vault_path)
-        .open_vault()
-        .unwrap();
+    if cli.is_serve() {
+        return web::go(vault, &bind_address).await;
+    }
+
+    if cli.run(&vault).is_err() {
+        std::process::exit(1);
+    }
 
-    web::go(vault).await
+    Ok(())
 }
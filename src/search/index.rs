@@ -0,0 +1,533 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{core::entity, util::hashing};
+
+use super::{query, tokenize};
+
+/// Bump this whenever the on-disk format changes, old indices are then rebuilt from scratch.
+const INDEX_VERSION: u32 = 3;
+
+const SNIPPET_TOKENS_BEFORE: usize = 8;
+const SNIPPET_TOKENS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FieldKind {
+    Title,
+    Alias,
+    FileName,
+    Heading,
+    Frontmatter,
+    Text,
+}
+
+impl FieldKind {
+    fn weight(&self) -> f64 {
+        match self {
+            FieldKind::Title => 5.0,
+            FieldKind::Alias => 4.0,
+            FieldKind::FileName => 3.0,
+            FieldKind::Heading => 2.0,
+            FieldKind::Frontmatter => 1.5,
+            FieldKind::Text => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Field {
+    pub kind: FieldKind,
+    pub text: String,
+}
+
+impl Field {
+    pub fn new(kind: FieldKind, text: String) -> Self {
+        Self { kind, text }
+    }
+}
+
+/// Everything the index remembers about a single entity.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndexedDocument {
+    pub fingerprint: hashing::Sha256,
+    pub title: String,
+    pub aliases: Vec<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Snippet {
+    pub text: String,
+    /// Byte ranges within `text` that matched the query.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+impl Snippet {
+    pub fn as_html(&self) -> maud::Markup {
+        let mut html = String::new();
+        let mut last = 0;
+
+        for (start, end) in &self.highlights {
+            html.push_str(&html_escape::encode_text(&self.text[last..*start]));
+            html.push_str("<mark>");
+            html.push_str(&html_escape::encode_text(&self.text[*start..*end]));
+            html.push_str("</mark>");
+            last = *end;
+        }
+
+        html.push_str(&html_escape::encode_text(&self.text[last..]));
+
+        maud::PreEscaped(html)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SearchHit {
+    pub id: entity::Id,
+    pub title: String,
+    pub aliases: Vec<String>,
+    pub score: f64,
+    pub snippet: Option<Snippet>,
+}
+
+/// Where a word occurs, the field is part of it so that phrases never span two fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+struct Position {
+    field: u16,
+    token: u32,
+}
+
+impl Position {
+    /// The position of the word `distance` words further in the same field.
+    fn after(self, distance: u32) -> Option<Position> {
+        Some(Position {
+            field: self.field,
+            token: self.token.checked_add(distance)?,
+        })
+    }
+}
+
+type Postings = HashMap<entity::Id, Vec<Position>>;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct InvertedIndex {
+    version: u32,
+    documents: HashMap<entity::Id, IndexedDocument>,
+    terms: BTreeMap<String, Postings>,
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    synchronized: bool,
+}
+
+impl InvertedIndex {
+    pub fn new() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            documents: HashMap::new(),
+            terms: BTreeMap::new(),
+            dirty: false,
+            synchronized: false,
+        }
+    }
+
+    fn index_file(base_path: &std::path::Path) -> std::path::PathBuf {
+        base_path.join("index.json")
+    }
+
+    /// Load the index stored in the given directory, or start with an empty one.
+    pub fn open(base_path: &std::path::Path) -> Self {
+        let loaded = std::fs::File::open(Self::index_file(base_path))
+            .ok()
+            .and_then(|file| {
+                serde_json::from_reader::<_, Self>(std::io::BufReader::new(file)).ok()
+            })
+            .filter(|index| index.version == INDEX_VERSION);
+
+        loaded.unwrap_or_default()
+    }
+
+    pub fn save(&mut self, base_path: &std::path::Path) -> Result<(), std::io::Error> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(base_path)?;
+
+        let file = std::fs::File::create(Self::index_file(base_path))?;
+        let writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(writer, self)?;
+
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    /// Whether the index has been compared against the vault since it was loaded.
+    pub fn is_synchronized(&self) -> bool {
+        self.synchronized
+    }
+
+    pub fn mark_synchronized(&mut self) {
+        self.synchronized = true;
+    }
+
+    pub fn fingerprint(&self, id: &entity::Id) -> Option<&hashing::Sha256> {
+        self.documents.get(id).map(|document| &document.fingerprint)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &entity::Id> {
+        self.documents.keys()
+    }
+
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn insert(&mut self, id: entity::Id, document: IndexedDocument) {
+        self.remove(&id);
+
+        for (field_index, field) in document.fields.iter().enumerate() {
+            let Ok(field_index) = u16::try_from(field_index) else {
+                eprintln!("Only the first {} fields of {} are searchable", u16::MAX as usize + 1, id.as_readable_string());
+                break;
+            };
+
+            for (token_index, token) in tokenize(&field.text).into_iter().enumerate() {
+                let Ok(token_index) = u32::try_from(token_index) else {
+                    eprintln!("Only the first {} words of a field of {} are searchable", u32::MAX as u64 + 1, id.as_readable_string());
+                    break;
+                };
+
+                let position = Position {
+                    field: field_index,
+                    token: token_index,
+                };
+
+                self.terms
+                    .entry(token.text)
+                    .or_default()
+                    .entry(id.clone())
+                    .or_default()
+                    .push(position);
+            }
+        }
+
+        self.documents.insert(id, document);
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, id: &entity::Id) {
+        let document = match self.documents.remove(id) {
+            Some(document) => document,
+            None => return,
+        };
+
+        let words = document
+            .fields
+            .iter()
+            .flat_map(|field| tokenize(&field.text))
+            .map(|token| token.text)
+            .collect::<HashSet<_>>();
+
+        for word in words {
+            if let Some(postings) = self.terms.get_mut(&word) {
+                postings.remove(id);
+
+                if postings.is_empty() {
+                    self.terms.remove(&word);
+                }
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    fn postings_for_prefix(&self, prefix: &str) -> Postings {
+        let mut merged: Postings = HashMap::new();
+
+        for (_, postings) in self
+            .terms
+            .range(prefix.to_string()..)
+            .take_while(|(term, _)| term.starts_with(prefix))
+        {
+            for (id, positions) in postings {
+                merged.entry(id.clone()).or_default().extend(positions);
+            }
+        }
+
+        merged
+    }
+
+    fn postings_for_phrase(&self, words: &[String]) -> Postings {
+        let mut matched: Postings = HashMap::new();
+
+        let first = match words.first().and_then(|word| self.terms.get(word)) {
+            Some(first) => first,
+            None => return matched,
+        };
+
+        let rest = words[1..]
+            .iter()
+            .map(|word| self.terms.get(word))
+            .collect::<Option<Vec<_>>>();
+
+        let rest = match rest {
+            Some(rest) => rest,
+            None => return matched,
+        };
+
+        for (id, positions) in first {
+            for &position in positions {
+                let follows = rest.iter().enumerate().all(|(offset, postings)| {
+                    postings
+                        .get(id)
+                        .zip(position.after(offset as u32 + 1))
+                        .map(|(p, next)| p.contains(&next))
+                        .unwrap_or(false)
+                });
+
+                if follows {
+                    let entry = matched.entry(id.clone()).or_default();
+                    entry.extend((0..words.len() as u32).filter_map(|offset| position.after(offset)));
+                }
+            }
+        }
+
+        matched
+    }
+
+    fn postings_for_term(&self, term: &query::Term) -> Postings {
+        match term {
+            query::Term::Word(word) => self.terms.get(word).cloned().unwrap_or_default(),
+            query::Term::Prefix(prefix) => self.postings_for_prefix(prefix),
+            query::Term::Phrase(words) => self.postings_for_phrase(words),
+        }
+    }
+
+    fn field_of(&self, document: &IndexedDocument, position: Position) -> Option<FieldKind> {
+        document
+            .fields
+            .get(position.field as usize)
+            .map(|field| field.kind)
+    }
+
+    fn generate_snippet(document: &IndexedDocument, positions: &[Position]) -> Option<Snippet> {
+        // Prefer showing the body text, fall back to whatever field matched first
+        let field_index = positions
+            .iter()
+            .map(|position| position.field as usize)
+            .min_by_key(|index| {
+                let is_text = document.fields.get(*index).map(|f| f.kind) == Some(FieldKind::Text);
+                (!is_text, *index)
+            })?;
+
+        let field = document.fields.get(field_index)?;
+        let tokens = tokenize(&field.text);
+
+        let matched = positions
+            .iter()
+            .filter(|position| position.field as usize == field_index)
+            .map(|position| position.token as usize)
+            .collect::<HashSet<_>>();
+
+        let first_match = *matched.iter().min()?;
+        let first_token = first_match.saturating_sub(SNIPPET_TOKENS_BEFORE);
+        let last_token = (first_token + SNIPPET_TOKENS).min(tokens.len()) - 1;
+
+        let start = if first_token == 0 { 0 } else { tokens[first_token].start };
+        let end = if last_token + 1 == tokens.len() { field.text.len() } else { tokens[last_token].end };
+
+        let mut text = String::new();
+
+        if start > 0 {
+            text.push('…');
+        }
+
+        let offset = text.len();
+        text.push_str(&field.text[start..end].replace('\n', " "));
+
+        let highlights = (first_token..=last_token)
+            .filter(|index| matched.contains(index))
+            .map(|index| (tokens[index].start - start + offset, tokens[index].end - start + offset))
+            .collect();
+
+        if end < field.text.len() {
+            text.push('…');
+        }
+
+        Some(Snippet { text, highlights })
+    }
+
+    pub fn search(&self, query: &query::Query) -> Vec<SearchHit> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let total = self.documents.len() as f64;
+
+        let mut candidates: Option<HashMap<entity::Id, (f64, Vec<Position>)>> = None;
+
+        for term in &query.terms {
+            let postings = self.postings_for_term(term);
+            let idf = (1.0 + total / (postings.len() as f64).max(1.0)).ln();
+
+            let scored = postings
+                .into_iter()
+                .filter_map(|(id, positions)| {
+                    let document = self.documents.get(&id)?;
+                    let weight = positions
+                        .iter()
+                        .filter_map(|position| self.field_of(document, *position))
+                        .map(|kind| kind.weight())
+                        .sum::<f64>();
+                    Some((id, (weight * idf, positions)))
+                })
+                .collect::<HashMap<_, _>>();
+
+            candidates = Some(match candidates {
+                None => scored,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(id, (score, mut positions))| {
+                        let (other_score, other_positions) = scored.get(&id)?;
+                        positions.extend(other_positions);
+                        Some((id, (score + other_score, positions)))
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut hits = candidates
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, (score, positions))| {
+                let document = self.documents.get(&id)?;
+                Some(SearchHit {
+                    snippet: Self::generate_snippet(document, &positions),
+                    title: document.title.clone(),
+                    aliases: document.aliases.clone(),
+                    score,
+                    id,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.title.cmp(&b.title))
+        });
+
+        hits
+    }
+}
+
+impl Default for InvertedIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, FieldKind, IndexedDocument, InvertedIndex};
+    use crate::{core::entity::Id, search::Query, util::hashing::Sha256};
+
+    fn document(title: &str, fields: Vec<Field>) -> IndexedDocument {
+        IndexedDocument {
+            fingerprint: Sha256::hash_bytes(title.as_bytes()),
+            title: title.to_string(),
+            aliases: vec![],
+            fields: std::iter::once(Field::new(FieldKind::Title, title.to_string())).chain(fields).collect(),
+        }
+    }
+
+    fn text(text: &str) -> Field {
+        Field::new(FieldKind::Text, text.to_string())
+    }
+
+    fn found(index: &InvertedIndex, query: &str) -> Vec<String> {
+        index
+            .search(&Query::parse(query))
+            .into_iter()
+            .map(|hit| hit.title)
+            .collect()
+    }
+
+    fn example() -> InvertedIndex {
+        let mut index = InvertedIndex::new();
+
+        index.insert(Id::Basic("a".to_string()), document("Borrowing", vec![text("The borrow checker checks borrows.")]));
+        index.insert(Id::Basic("b".to_string()), document("Checker", vec![text("A checker for the borrow rules")]));
+        index.insert(Id::Basic("c".to_string()), document("Unrelated", vec![text("Nothing to see")]));
+
+        index
+    }
+
+    #[test]
+    fn words_prefixes_and_phrases_match() {
+        let index = example();
+
+        // The title weighs more than the text
+        assert_eq!(found(&index, "checker"), vec!["Checker", "Borrowing"]);
+        assert_eq!(found(&index, "borrow*"), vec!["Borrowing", "Checker"]);
+        assert_eq!(found(&index, "\"borrow checker\""), vec!["Borrowing"]);
+        assert_eq!(found(&index, "borrow see"), Vec::<String>::new());
+        assert_eq!(found(&index, "\"checker borrow\""), Vec::<String>::new());
+
+        // Phrases don't continue from the title into the text
+        assert_eq!(found(&index, "\"unrelated nothing\""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn removed_documents_are_not_found() {
+        let mut index = example();
+        let terms = index.term_count();
+
+        index.remove(&Id::Basic("c".to_string()));
+
+        assert!(found(&index, "nothing").is_empty());
+        assert_eq!(index.document_count(), 2);
+        assert_eq!(index.term_count(), terms - 4);
+    }
+
+    #[test]
+    fn snippets_highlight_the_matches_in_the_text() {
+        let mut index = InvertedIndex::new();
+        let long = (0..50).map(|i| format!("w{}", i)).collect::<Vec<_>>().join(" ");
+
+        index.insert(Id::Basic("a".to_string()), document("Title", vec![text(&long)]));
+
+        let hits = index.search(&Query::parse("\"w20 w21\""));
+        let snippet = hits[0].snippet.as_ref().unwrap();
+
+        assert!(snippet.text.starts_with("…w12 "), "{}", snippet.text);
+        assert!(snippet.text.ends_with(" w43…"), "{}", snippet.text);
+
+        let highlighted = snippet.highlights.iter().map(|(start, end)| &snippet.text[*start..*end]).collect::<Vec<_>>();
+        assert_eq!(highlighted, vec!["w20", "w21"]);
+        assert_eq!(snippet.as_html().into_string().matches("<mark>").count(), 2);
+
+        // Only the title matched, so that is shown
+        let hits = index.search(&Query::parse("title"));
+        assert_eq!(hits[0].snippet.as_ref().unwrap().text, "Title");
+    }
+
+    #[test]
+    fn positions_in_many_and_long_fields() {
+        let mut index = InvertedIndex::new();
+
+        let mut fields = (0..5000).map(|i| text(&format!("filler{}", i))).collect::<Vec<_>>();
+        fields.push(text(&format!("{} needle in the haystack", "hay ".repeat(1 << 20))));
+
+        index.insert(Id::Basic("a".to_string()), document("Big", fields));
+
+        assert_eq!(found(&index, "\"needle in\""), vec!["Big"]);
+        assert_eq!(found(&index, "\"filler4999 needle\""), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,64 @@
+
+pub mod index;
+pub mod query;
+
+pub use index::{Field, FieldKind, IndexedDocument, InvertedIndex, SearchHit, Snippet};
+pub use query::Query;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Split a text into lowercase words, remembering the byte range each word came from.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (index, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(s)) => {
+                tokens.push(Token {
+                    text: text[s..index].to_lowercase(),
+                    start: s,
+                    end: index,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        tokens.push(Token {
+            text: text[s..].to_lowercase(),
+            start: s,
+            end: text.len(),
+        });
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tokenize;
+
+    #[test]
+    fn tokens_are_lowercase_words_with_their_bytes() {
+        let text = "Hello, Wörld! it's 2024-05";
+        let tokens = tokenize(text);
+
+        let words = tokens.iter().map(|token| token.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["hello", "wörld", "it", "s", "2024", "05"]);
+
+        assert_eq!(&text[tokens[1].start..tokens[1].end], "Wörld");
+        assert_eq!(tokens.last().unwrap().end, text.len());
+
+        assert!(tokenize(" -- ").is_empty());
+    }
+}
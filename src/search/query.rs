@@ -0,0 +1,82 @@
+use super::tokenize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Word(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+/// A parsed search query. All terms have to match for a document to be found.
+///
+/// Supported syntax:
+///  - `word` matches the word anywhere in the document
+///  - `wor*` matches every word starting with `wor`
+///  - `"some phrase"` matches the words in exactly this order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Query {
+        fn words_to_term(words: Vec<String>, prefix: bool) -> Option<Term> {
+            match words.len() {
+                0 => None,
+                1 if prefix => Some(Term::Prefix(words[0].clone())),
+                1 => Some(Term::Word(words[0].clone())),
+                _ => Some(Term::Phrase(words)),
+            }
+        }
+
+        fn words_of(text: &str) -> Vec<String> {
+            tokenize(text).into_iter().map(|token| token.text).collect()
+        }
+
+        let mut terms = Vec::new();
+        let mut rest = query.trim();
+
+        while !rest.is_empty() {
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let (phrase, remainder) = quoted.split_once('"').unwrap_or((quoted, ""));
+                terms.extend(words_to_term(words_of(phrase), false));
+                rest = remainder.trim_start();
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let (word, remainder) = rest.split_at(end);
+                terms.extend(words_to_term(words_of(word), word.ends_with('*')));
+                rest = remainder.trim_start();
+            }
+        }
+
+        Query { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, Term};
+
+    #[test]
+    fn words_prefixes_and_phrases() {
+        let query = Query::parse(" Rust  asyn* \"the Borrow checker\" \"single\" foo-bar \"unclosed phrase");
+
+        assert_eq!(
+            query.terms,
+            vec![
+                Term::Word("rust".to_string()),
+                Term::Prefix("asyn".to_string()),
+                Term::Phrase(vec!["the".to_string(), "borrow".to_string(), "checker".to_string()]),
+                Term::Word("single".to_string()),
+                Term::Phrase(vec!["foo".to_string(), "bar".to_string()]),
+                Term::Phrase(vec!["unclosed".to_string(), "phrase".to_string()]),
+            ]
+        );
+
+        assert!(Query::parse("  \"\" * ").is_empty());
+    }
+}
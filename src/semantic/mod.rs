@@ -1,12 +1,21 @@
-use crate::core::entity::link::reference::Reference;
+use crate::core::entity::{link::reference::Reference, zettel::document::block::bullet_point::TaskInfo};
 
 
 pub mod knowledge;
 
 
+/// A task as found in a document. Which entity it belongs to is up to the one scanning.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Task {
+    pub info: TaskInfo,
+    pub text: String,
+    /// The tags written in the task itself.
+    pub tags: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub enum InfoItem {
-    Task,
+    Task(Task),
     Tag(String),
     Link(Reference),
 }
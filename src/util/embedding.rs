@@ -143,13 +143,9 @@ pub fn embed_file_for_id<S: ToString>(
             }
         }
         resource::Type::Other(resource::types::OtherType::Email) => {
-            // Create a code block with the email content
-            if let Ok(content) = std::str::from_utf8(file.content()) {
-                maud::html! {
-                    pre { (content) }
-                }
-            } else {
-                content_not_displayed()
+            match entity::email::Email::from_rfc822(file.content()) {
+                Ok(email) => email.body_as_html(id),
+                Err(_) => content_not_displayed(),
             }
         }
         _ => content_not_displayed(),
@@ -165,6 +161,7 @@ pub fn embed_entity_for_id(
 
     match entity {
         entity::Entity::File(file) => embed_file_for_id(file, id, "Untitled", false),
+        entity::Entity::Email(email) => email.body_as_html(id),
         entity::Entity::Zettel(zettel) => {
             if let Some(content) = zettel.body_as_document() {
                 let html = content.as_html(conversion_context);
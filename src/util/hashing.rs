@@ -10,6 +10,9 @@ impl Sha256 {
 
     pub(crate) fn from_string<S: ToString>(string: S) -> Result<Self, ()> {
         let bytes = hex::decode(string.to_string()).map_err(|_| ())?;
+        if bytes.len() != 32 {
+            return Err(());
+        }
         let mut sha256_bytes = [0; 32];
         sha256_bytes.copy_from_slice(&bytes);
         Ok(Sha256::new(sha256_bytes))
@@ -1,13 +1,54 @@
 pub mod embedding;
 pub mod hashing;
+pub mod span;
+
+#[cfg(test)]
+pub(crate) mod testing;
+
+/// Percent-encode everything but the unreserved characters, e.g. for a single segment of a URI path.
+pub fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
 
 pub fn split_metadata_from_content(content: String) -> (Option<String>, String) {
-    let (header, body) = content
-        .split_once("---")
-        .and_then(|(_, rest)| rest.split_once("---"))
-        .map_or((None, content.clone()), |(header, body)| {
-            (Some(header.trim().to_string()), body.to_string())
-        });
-
-    (header, body)
+    // The frontmatter has to start in the very first line, anywhere else `---` is a thematic break
+    let rest = match content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n")) {
+        Some(rest) => rest,
+        None => return (None, content),
+    };
+
+    let mut offset = 0;
+
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let header = rest[..offset].trim().to_string();
+            let body = rest[offset + line.len()..].to_string();
+            return (Some(header), body);
+        }
+
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percent_encode;
+
+    #[test]
+    fn only_unreserved_characters_stay() {
+        assert_eq!(percent_encode("a-b_c.d~1"), "a-b_c.d~1");
+        assert_eq!(percent_encode("a b?#/%"), "a%20b%3F%23%2F%25");
+        assert_eq!(percent_encode("größe"), "gr%C3%B6%C3%9Fe");
+    }
 }
@@ -0,0 +1,74 @@
+/// A position in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize)]
+pub struct Position {
+    /// Bytes from the start of the file.
+    pub offset: usize,
+    /// The line, counting from 0.
+    pub line: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize) -> Position {
+        Position { offset, line }
+    }
+
+    /// The position after `text`, if it starts at this position.
+    pub fn advanced_by(&self, text: &str) -> Position {
+        Position {
+            offset: self.offset + text.len(),
+            line: self.line + text.matches('\n').count(),
+        }
+    }
+}
+
+/// The part of a source file something was parsed from. The end is not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Span {
+        Span { start, end }
+    }
+
+    /// The byte range, e.g. to cut the text out of the source.
+    pub fn bytes(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// The lines touched by the span.
+    pub fn lines(&self) -> std::ops::RangeInclusive<usize> {
+        self.start.line..=self.end.line
+    }
+}
+
+/// A value together with the place in the source it comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct Spanned<T> {
+    #[serde(flatten)]
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Spanned<T> {
+        Spanned { value, span }
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, func: F) -> Spanned<U> {
+        Spanned {
+            value: func(self.value),
+            span: self.span,
+        }
+    }
+}
+
+impl<T> std::ops::Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
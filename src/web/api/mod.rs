@@ -0,0 +1,452 @@
+use actix_web::{http::StatusCode, web, HttpResponse};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::core::{
+    entity::{
+        self, email,
+        zettel::{self, document::conversions::html::{AsHtml, HtmlConversionContext}},
+    },
+    vault::{self, resource, tasks},
+};
+
+//
+// Everything below /api/v1/ answers with JSON, including the errors.
+//
+
+#[derive(serde::Serialize)]
+struct Error {
+    error: String,
+}
+
+fn error_response(code: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(code).json(Error {
+        error: message.to_string(),
+    })
+}
+
+fn not_found() -> HttpResponse {
+    error_response(StatusCode::NOT_FOUND, "Entity not found")
+}
+
+fn with_parsed_id<F>(vault: &vault::Vault, id: &str, func: F) -> HttpResponse
+where
+    F: FnOnce(entity::Id) -> HttpResponse,
+{
+    let id = match entity::Id::with_id(id) {
+        Ok(id) => id,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Invalid ID format"),
+    };
+
+    // Attachments are never listed, so they don't have a canonical ID of their own
+    let id = match id {
+        entity::Id::Attachment(..) => Some(id),
+        _ => vault.canonical_id(&id),
+    };
+
+    match id {
+        Some(id) => func(id),
+        None => not_found(),
+    }
+}
+
+fn resource_type_of(vault: &vault::Vault, id: &entity::Id) -> Option<resource::Type> {
+    match id {
+        entity::Id::Attachment(parent, index) => vault
+            .load_attachment(parent, *index)
+            .map(|file| file.metadata().file_type()),
+        _ => vault.load_resource(id)?.resource_type(),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct EntitySummary {
+    id: entity::Id,
+    title: String,
+    resource_type: Option<resource::Type>,
+    uri: String,
+}
+
+impl EntitySummary {
+    fn of(vault: &vault::Vault, id: entity::Id) -> Self {
+        Self {
+            title: vault
+                .title_of_entity(&id)
+                .unwrap_or_else(|| id.as_readable_string()),
+            resource_type: resource_type_of(vault, &id),
+            uri: id.as_safe_uri(),
+            id,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct HeaderLine<'a> {
+    key: &'a str,
+    value: &'a zettel::parts::header::Line,
+}
+
+#[derive(serde::Serialize)]
+struct Attachment<'a> {
+    id: entity::Id,
+    name: Option<&'a str>,
+    mime_type: &'static str,
+}
+
+#[derive(serde::Serialize)]
+struct EntityDetails<'a> {
+    #[serde(flatten)]
+    summary: EntitySummary,
+    mime_type: Option<&'static str>,
+    raw_uri: String,
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header: Option<Vec<HeaderLine<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<&'a email::Headers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attachments: Option<Vec<Attachment<'a>>>,
+}
+
+/// Describe a single entity, together with its frontmatter or email headers.
+pub fn generate_entity_json(vault: &vault::Vault, id: entity::Id) -> HttpResponse {
+    let entity = match vault.load_entity(&id) {
+        Some(entity) => entity,
+        None => return not_found(),
+    };
+
+    let summary = EntitySummary::of(vault, id.clone());
+
+    let mut details = EntityDetails {
+        mime_type: summary.resource_type.map(|resource_type| resource_type.mime_type()),
+        raw_uri: id.as_safe_download_uri(),
+        tags: vault.tags_of_entity(&id),
+        header: None,
+        email: None,
+        attachments: None,
+        summary,
+    };
+
+    match &entity {
+        entity::Entity::Zettel(zettel) => {
+            details.header = Some(
+                zettel
+                    .header
+                    .lines
+                    .iter()
+                    .map(|(key, value)| HeaderLine { key, value })
+                    .collect(),
+            );
+        }
+        entity::Entity::Email(email) => {
+            details.email = Some(&email.headers);
+            details.attachments = Some(
+                email
+                    .attachments()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, attachment)| Attachment {
+                        id: id.attachment(index),
+                        name: attachment.name.as_deref(),
+                        mime_type: attachment.resource_type().mime_type(),
+                    })
+                    .collect(),
+            );
+        }
+        entity::Entity::File(_) => {}
+    }
+
+    HttpResponse::Ok().json(details)
+}
+
+pub async fn list_entities(vault: web::Data<Arc<vault::Vault>>) -> HttpResponse {
+    let mut entities = vault
+        .list_entities()
+        .into_iter()
+        .map(|id| EntitySummary::of(&vault, id))
+        .collect::<Vec<_>>();
+
+    entities.sort_by(|a, b| a.title.cmp(&b.title));
+
+    HttpResponse::Ok().json(entities)
+}
+
+/// The parts of an entity are selected through `?show=`, so that they can't be confused with an ID ending in `/links`.
+pub async fn show_entity(
+    vault: web::Data<Arc<vault::Vault>>,
+    id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let generate = match query.get("show").map(|s| s.as_str()) {
+        None => generate_entity_json,
+        Some("document") => generate_document_json,
+        Some("links") => generate_links_json,
+        Some(_) => return error_response(StatusCode::BAD_REQUEST, "`show` has to be `document` or `links`"),
+    };
+
+    with_parsed_id(&vault, &id.into_inner(), |id| generate(&vault, id))
+}
+
+fn generate_document_json(vault: &vault::Vault, id: entity::Id) -> HttpResponse {
+    #[derive(serde::Serialize)]
+    struct Response<'a> {
+        id: &'a entity::Id,
+        /// `None` if the zettel has no body.
+        document: Option<&'a entity::zettel::document::Document>,
+    }
+
+    match vault.load_zettel(&id) {
+        Some(zettel) => HttpResponse::Ok().json(Response {
+            id: &id,
+            document: zettel.body_as_document(),
+        }),
+        None => error_response(StatusCode::NOT_FOUND, "Entity is not a zettel"),
+    }
+}
+
+fn generate_links_json(vault: &vault::Vault, id: entity::Id) -> HttpResponse {
+    #[derive(serde::Serialize)]
+    struct Backlink {
+        #[serde(flatten)]
+        source: EntitySummary,
+        contexts: Vec<String>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Response {
+        id: entity::Id,
+        outgoing: Vec<EntitySummary>,
+        backlinks: Vec<Backlink>,
+    }
+
+    let mut targets = Vec::new();
+
+    for (target, _) in vault.outgoing_links(&id) {
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    let backlinks = vault
+        .backlinks(&id)
+        .into_iter()
+        .map(|backlink| Backlink {
+            source: EntitySummary::of(vault, backlink.source),
+            contexts: backlink.contexts,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(Response {
+        outgoing: targets
+            .into_iter()
+            .map(|target| EntitySummary::of(vault, target))
+            .collect(),
+        backlinks,
+        id,
+    })
+}
+
+#[derive(serde::Deserialize)]
+pub struct ToggleTask {
+    /// The line of the task in the source, counting from 0.
+    line: usize,
+    /// The hash of the source the caller has seen.
+    hash: String,
+}
+
+fn toggle_error_response(error: tasks::ToggleError) -> HttpResponse {
+    match error {
+        tasks::ToggleError::NotFound => error_response(StatusCode::NOT_FOUND, "Entity is not a zettel"),
+        tasks::ToggleError::Conflict => error_response(StatusCode::CONFLICT, "The entity has been changed in the meantime"),
+        tasks::ToggleError::NoTask => error_response(StatusCode::BAD_REQUEST, "There is no task in this line"),
+        tasks::ToggleError::Io(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("Unable to save: {}", e)),
+    }
+}
+
+/// Changes to an entity are selected through `?action=`, currently only `toggle_task`.
+pub async fn post_entity(
+    vault: web::Data<Arc<vault::Vault>>,
+    id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Json<ToggleTask>,
+) -> HttpResponse {
+    match query.get("action").map(|s| s.as_str()) {
+        Some("toggle_task") => toggle_task(&vault, &id.into_inner(), &body),
+        _ => error_response(StatusCode::BAD_REQUEST, "`action` has to be `toggle_task`"),
+    }
+}
+
+/// Toggle a task and answer with the new hash and the rendered body of the zettel.
+fn toggle_task(vault: &Arc<vault::Vault>, id: &str, body: &ToggleTask) -> HttpResponse {
+    #[derive(serde::Serialize)]
+    struct Response {
+        hash: String,
+        html: String,
+    }
+
+    with_parsed_id(vault, id, |id| {
+        let hash = match vault.toggle_task(&id, body.line, &body.hash) {
+            Ok(hash) => hash,
+            Err(error) => return toggle_error_response(error),
+        };
+
+        let context = HtmlConversionContext::new(Arc::clone(vault))
+            .with_editable_tasks()
+            .for_entity(&id);
+
+        let html = vault
+            .load_zettel(&id)
+            .and_then(|zettel| zettel.body_as_document().map(|document| document.as_html(&context)))
+            .unwrap_or_default();
+
+        HttpResponse::Ok().json(Response { hash, html })
+    })
+}
+
+pub async fn list_tags(vault: web::Data<Arc<vault::Vault>>) -> HttpResponse {
+    #[derive(serde::Serialize)]
+    struct Tag {
+        tag: String,
+        count: usize,
+    }
+
+    let tags = vault
+        .tags()
+        .into_iter()
+        .map(|(tag, count)| Tag { tag, count })
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok().json(tags)
+}
+
+pub async fn show_tag(vault: web::Data<Arc<vault::Vault>>, tag: web::Path<String>) -> HttpResponse {
+    let entities = vault
+        .entities_with_tag(&tag.into_inner())
+        .into_iter()
+        .map(|id| EntitySummary::of(&vault, id))
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok().json(entities)
+}
+
+pub async fn search(
+    vault: web::Data<Arc<vault::Vault>>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    match query.get("q") {
+        Some(q) => HttpResponse::Ok().json(vault.search(q)),
+        None => error_response(StatusCode::BAD_REQUEST, "Missing query parameter `q`"),
+    }
+}
+
+pub async fn not_found_route() -> HttpResponse {
+    error_response(StatusCode::NOT_FOUND, "No such endpoint")
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, HttpResponse};
+
+    use super::{generate_entity_json, toggle_error_response};
+    use crate::core::{
+        config::{ConfigBuilder, VolumeConfig, VolumeKind},
+        entity::Id,
+        vault::{import::ImportOptions, tasks::ToggleError, volume::flags::Flags, Vault},
+    };
+    use crate::util::testing::TempDir;
+
+    const EMAIL: &str = "From: Alice <alice@example.com>\r\n\
+        To: bob@example.com\r\n\
+        Subject: Report\r\n\
+        Message-ID: <report@example.com>\r\n\
+        MIME-Version: 1.0\r\n\
+        Content-Type: multipart/mixed; boundary=\"b\"\r\n\
+        \r\n\
+        --b\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        See the attachment.\r\n\
+        --b\r\n\
+        Content-Type: application/pdf\r\n\
+        Content-Disposition: attachment; filename=\"report.pdf\"\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        \r\n\
+        JVBERi0xLjQK\r\n\
+        --b--\r\n";
+
+    fn open_vault(dir: &TempDir) -> Vault {
+        dir.write("vault/note.md", "---\ntitle: A note\ntags: [api]\n---\nBody with #inline\n");
+        std::fs::create_dir_all(dir.path().join("mail")).unwrap();
+
+        ConfigBuilder::new()
+            .cache_path(dir.path().join("cache"))
+            .vault_path(dir.path().join("vault"))
+            .volume(VolumeConfig::new(VolumeKind::Email, dir.path().join("mail"), Flags::new()))
+            .open_vault()
+            .unwrap()
+    }
+
+    async fn json_of(response: HttpResponse) -> (StatusCode, serde_json::Value) {
+        let status = response.status();
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn zettels_are_described_with_their_frontmatter() {
+        let dir = TempDir::new("api-zettel");
+        let vault = open_vault(&dir);
+
+        let (status, json) = json_of(generate_entity_json(&vault, Id::Basic("note".to_string()))).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["title"], "A note");
+        assert_eq!(json["uri"], "/entity/note");
+        assert_eq!(json["raw_uri"], "/raw/note");
+        assert_eq!(json["mime_type"], "text/markdown");
+        assert!(json["tags"].as_array().unwrap().contains(&"api".into()), "{}", json);
+        assert_eq!(json["header"][0]["key"], "title");
+        assert!(json.get("email").is_none() && json.get("attachments").is_none(), "{}", json);
+
+        let (status, json) = json_of(generate_entity_json(&vault, Id::Basic("missing".to_string()))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json, serde_json::json!({ "error": "Entity not found" }));
+    }
+
+    #[actix_web::test]
+    async fn emails_are_described_with_their_headers_and_attachments() {
+        let dir = TempDir::new("api-email");
+        let vault = open_vault(&dir);
+
+        let source = dir.write("report.eml", EMAIL);
+        let report = vault.import_emails(&source, &ImportOptions::default());
+        let id = Id::Email(report.imported[0].clone());
+
+        let (status, json) = json_of(generate_entity_json(&vault, id.clone())).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["uri"], id.as_safe_uri());
+        assert!(json.get("header").is_none(), "{}", json);
+        assert!(json["email"].is_object(), "{}", json);
+        assert_eq!(json["attachments"][0]["name"], "report.pdf");
+        assert_eq!(json["attachments"][0]["mime_type"], "application/pdf");
+        assert_eq!(json["attachments"][0]["id"], serde_json::to_value(id.attachment(0)).unwrap());
+    }
+
+    #[actix_web::test]
+    async fn toggle_errors_map_to_statuses() {
+        let cases = [
+            (ToggleError::NotFound, StatusCode::NOT_FOUND),
+            (ToggleError::Conflict, StatusCode::CONFLICT),
+            (ToggleError::NoTask, StatusCode::BAD_REQUEST),
+            (ToggleError::Io(std::io::Error::other("disk full")), StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+
+        for (error, expected) in cases {
+            let (status, json) = json_of(toggle_error_response(error)).await;
+
+            assert_eq!(status, expected);
+            assert!(json["error"].is_string(), "{}", json);
+        }
+    }
+}
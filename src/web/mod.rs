@@ -5,22 +5,31 @@ use tokio::time::{interval, Duration};
 
 use crate::core::vault;
 
+pub mod api;
 pub mod routes;
 pub mod pages;
 
 
 async fn run_periodic_task(vault: Arc<vault::Vault>) {
-    let mut interval = interval(Duration::from_secs(60)); // Run every 60 seconds
+    let mut interval = interval(Duration::from_secs(2)); // Pick up file changes every 2 seconds
+    let mut iteration: u64 = 0;
 
     loop {
         interval.tick().await;
 
-        println!("Running periodic task on the vault...");
-        vault.tick();
+        // The full tick (polling, flushing the cache) only runs every 60 seconds
+        if iteration.is_multiple_of(30) {
+            println!("Running periodic task on the vault...");
+            vault.tick();
+        } else {
+            vault.process_events();
+        }
+
+        iteration += 1;
     }
 }
 
-pub async fn go(vault: vault::Vault) -> std::io::Result<()> {
+pub async fn go(vault: vault::Vault, bind_address: &str) -> std::io::Result<()> {
     let vault_data = Arc::new(vault);
 
     {
@@ -38,11 +47,29 @@ pub async fn go(vault: vault::Vault) -> std::io::Result<()> {
                 .app_data(vault_data.clone())
                 .route("/", web::get().to(routes::list_entities))
                 .route("/web/{file}", web::get().to(routes::web_file))
-                .route("/entity/{id}", web::get().to(routes::process_entity))
-                .route("/entity/{id}", web::post().to(routes::post_entity))
-                .route("/raw/{id}", web::get().to(routes::download_entity))
+                .route("/entity/{id:.*}", web::get().to(routes::process_entity))
+                .route("/entity/{id:.*}", web::post().to(routes::post_entity))
+                .route("/raw/{id:.*}", web::get().to(routes::download_entity))
+                .route("/search", web::get().to(routes::search))
+                .route("/search.json", web::get().to(routes::search_json))
+                .route("/tags", web::get().to(routes::list_tags))
+                .route("/tags/{tag:.*}", web::get().to(routes::show_tag))
+                .route("/tasks", web::get().to(routes::list_tasks))
+                .route("/thread/{id:.*}", web::get().to(routes::show_thread))
+                .route("/import", web::get().to(routes::show_import))
+                .route("/import", web::post().to(routes::post_import))
+                .service(
+                    web::scope("/api/v1")
+                        .route("/entities", web::get().to(api::list_entities))
+                        .route("/entities/{id:.*}", web::get().to(api::show_entity))
+                        .route("/entities/{id:.*}", web::post().to(api::post_entity))
+                        .route("/tags", web::get().to(api::list_tags))
+                        .route("/tags/{tag:.*}", web::get().to(api::show_tag))
+                        .route("/search", web::get().to(api::search))
+                        .default_service(web::to(api::not_found_route)),
+                )
         })
-        .bind("127.0.0.1:8080")?
+        .bind(bind_address)?
         .run()
         .await
     }
@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use actix_web::HttpResponse;
+use maud::html;
+
+use crate::{
+    core::{
+        entity::{self, email},
+        vault::{self, resource},
+    },
+    web::pages::{self, decorate_content_page},
+};
+
+fn render_addresses(addresses: &[email::Address]) -> String {
+    addresses
+        .iter()
+        .map(|address| address.display())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Generate a HTML table containing the most important headers of an email.
+fn generate_headers_box(headers: &email::Headers) -> maud::PreEscaped<String> {
+    let mut rows = Vec::new();
+
+    if !headers.from.is_empty() {
+        rows.push(("From", render_addresses(&headers.from)));
+    }
+    if !headers.to.is_empty() {
+        rows.push(("To", render_addresses(&headers.to)));
+    }
+    if !headers.cc.is_empty() {
+        rows.push(("Cc", render_addresses(&headers.cc)));
+    }
+    if let Some(date) = &headers.date {
+        rows.push(("Date", date.format("%Y-%m-%d %H:%M %:z").to_string()));
+    }
+    if let Some(message_id) = &headers.message_id {
+        rows.push(("Message-ID", message_id.clone()));
+    }
+
+    html! {
+        table class="email-headers" {
+            @for (name, value) in rows {
+                tr {
+                    th { (name) }
+                    td { (value) }
+                }
+            }
+        }
+    }
+}
+
+/// List the attachments of an email. Images, PDFs and media are shown inline.
+fn generate_attachments_box(id: &entity::Id, email: &email::Email) -> maud::PreEscaped<String> {
+    if email.attachments().is_empty() {
+        return maud::PreEscaped(String::new());
+    }
+
+    html! {
+        div class="email-attachments" {
+            h2 { "Attachments" }
+            ul {
+                @for (index, attachment) in email.attachments().iter().enumerate() {
+                    @let attachment_id = id.attachment(index);
+                    @let file = attachment.to_file_content(index);
+                    @let title = file.metadata().title().unwrap_or_default();
+                    li {
+                        a href=(attachment_id.as_safe_uri()) { (title) }
+                        " ("
+                        code { (file.metadata().mime_type()) }
+                        ", " (file.content().len()) " bytes) "
+                        a href=(attachment_id.as_safe_download_uri()) { "Download" }
+
+                        @match file.metadata().file_type() {
+                            resource::Type::Image(_)
+                            | resource::Type::Audio(_)
+                            | resource::Type::Video(_)
+                            | resource::Type::Document(resource::types::DocumentType::Pdf) => {
+                                (crate::util::embedding::embed_file_for_id(&file, &attachment_id, &title, true))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn thread_uri(id: &entity::Id) -> String {
+    format!("/thread/{}", id.id())
+}
+
+fn link_to_email(vault: &Arc<vault::Vault>, id: &entity::Id) -> maud::PreEscaped<String> {
+    let title = vault
+        .title_of_entity(id)
+        .unwrap_or_else(|| id.as_readable_string());
+
+    html! {
+        a href=(id.as_safe_uri()) { (title) }
+    }
+}
+
+/// Links to the email this one replies to, the replies to it and the whole conversation.
+fn generate_thread_box(vault: &Arc<vault::Vault>, id: &entity::Id) -> maud::PreEscaped<String> {
+    let parent = vault.email_parent(id);
+    let replies = vault.email_replies(id);
+    let thread_size = vault.email_thread(id).len();
+
+    if parent.is_none() && replies.is_empty() && thread_size <= 1 {
+        return maud::PreEscaped(String::new());
+    }
+
+    html! {
+        div class="email-thread" {
+            @if let Some(parent) = &parent {
+                p { "In reply to: " (link_to_email(vault, parent)) }
+            }
+            @if !replies.is_empty() {
+                p { "Replies:" }
+                ul {
+                    @for reply in &replies {
+                        li { (link_to_email(vault, reply)) }
+                    }
+                }
+            }
+            p { a href=(thread_uri(id)) { "View conversation (" (thread_size) " messages)" } }
+        }
+    }
+}
+
+/// Render a plain text body, collapsing quoted passages (lines starting with `>`).
+fn generate_text_with_collapsed_quotes(text: &str) -> maud::PreEscaped<String> {
+    let mut blocks: Vec<(bool, Vec<&str>)> = Vec::new();
+
+    for line in text.lines() {
+        let is_quote = line.trim_start().starts_with('>');
+
+        match blocks.last_mut() {
+            Some((quoted, lines)) if *quoted == is_quote => lines.push(line),
+            _ => blocks.push((is_quote, vec![line])),
+        }
+    }
+
+    html! {
+        div class="email-body" {
+            @for (quoted, lines) in &blocks {
+                @if *quoted {
+                    details class="email-quote" {
+                        summary { "Quoted text (" (lines.len()) " lines)" }
+                        pre class="email-text" { (lines.join("\n")) }
+                    }
+                } @else {
+                    pre class="email-text" { (lines.join("\n").trim_matches('\n')) }
+                }
+            }
+        }
+    }
+}
+
+/// Show all messages of a conversation, oldest first.
+pub fn generate_thread_page(vault: &Arc<vault::Vault>, id: &entity::Id) -> HttpResponse {
+    let thread = vault.email_thread(id);
+
+    if thread.is_empty() {
+        return pages::error::generate_404();
+    }
+
+    let emails = thread
+        .iter()
+        .filter_map(|member| match vault.load_entity(member) {
+            Some(entity::Entity::Email(email)) => Some((member.clone(), email)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let title = emails
+        .first()
+        .and_then(|(_, email)| email.title())
+        .unwrap_or_else(|| "(no subject)".to_string());
+
+    let html = pages::decorate_maud_html(
+        &title,
+        decorate_content_page(html! {
+            h1 { (title) }
+            p { (emails.len()) " messages" }
+            @for (member, email) in &emails {
+                div class="email-thread-message" id=(member.id()) {
+                    h2 { (link_to_email(vault, member)) }
+                    p class="email-thread-meta" {
+                        (render_addresses(&email.headers.from))
+                        @if let Some(date) = &email.headers.date {
+                            " — " (date.format("%Y-%m-%d %H:%M %:z").to_string())
+                        }
+                    }
+                    @match email.text() {
+                        Some(text) => (generate_text_with_collapsed_quotes(&text)),
+                        None => (email.body_as_html(member)),
+                    }
+                }
+            }
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
+
+pub fn generate_show_email(vault: &Arc<vault::Vault>, id: entity::Id, email: email::Email) -> HttpResponse {
+    let title = email
+        .title()
+        .unwrap_or_else(|| "(no subject)".to_string());
+
+    let html = pages::decorate_maud_html(
+        &title,
+        decorate_content_page(html! {
+            h1 { (title) }
+            a href=(id.as_safe_download_uri()) { "Raw" }
+            (generate_headers_box(&email.headers))
+            (generate_thread_box(vault, &id))
+            (email.body_as_html(&id))
+            (generate_attachments_box(&id, &email))
+            (pages::generate_backlinks_box(vault, &id))
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
@@ -96,18 +96,32 @@ pub fn generate_show_zettel(
     id: entity::Id,
     zettel: zettel::Zettel,
 ) -> HttpResponse {
+    let document = zettel.body_as_document().unwrap();
+
+    // Without a title in the frontmatter the first heading of the body is shown instead
+    let heading_title = document.title();
+    let shows_heading = zettel.title().is_none() && heading_title.is_some();
+
     let title = zettel
         .title()
         .map(String::from)
+        .or(heading_title)
         .unwrap_or_else(|| id.as_readable_string());
 
+    let aliases = zettel.header.aliases();
+
     let conversion_context =
-        zettel::document::conversions::html::HtmlConversionContext::new(Arc::clone(vault));
+        zettel::document::conversions::html::HtmlConversionContext::new(Arc::clone(vault))
+            .with_editable_tasks()
+            .for_entity(&id);
 
-    let content = zettel
-        .body_as_document()
-        .unwrap()
-        .as_html(&conversion_context);
+    // Toggling a task only works as long as nobody else changed the source
+    let hash = vault
+        .load_zettel_source(&id)
+        .map(|source| vault::tasks::source_hash(&source))
+        .unwrap_or_default();
+
+    let content = document.as_html(&conversion_context);
 
     {
         use crate::semantic;
@@ -124,7 +138,12 @@ pub fn generate_show_zettel(
     let html = pages::decorate_maud_html(
         &title,
         decorate_content_page(html! {
-            h1 { (title) }
+            @if !shows_heading {
+                h1 { (title) }
+            }
+            @if !aliases.is_empty() {
+                p class="aliases" { "Also known as: " (aliases.join(", ")) }
+            }
             a href=(format!("{}?action=edit", id.as_safe_uri())) { "Edit" }
             " "
             a href=(format!("{}", id.as_safe_download_uri())) { "Raw" }
@@ -133,7 +152,11 @@ pub fn generate_show_zettel(
             br;
             (generate_metadata_box(&zettel.header, &conversion_context))
             br;
-            (maud::PreEscaped(content))
+            div class="zettel-body" data-toggle-uri=(format!("/api/v1/entities/{}?action=toggle_task", id.id())) data-hash=(hash) {
+                (maud::PreEscaped(content))
+            }
+            (pages::generate_backlinks_box(vault, &id))
+            script src="/web/tasks.js" {}
         }),
     );
 
@@ -145,26 +168,29 @@ pub async fn edit_zettel(
     id: web::Path<String>,
 ) -> HttpResponse {
     pages::generate_page_with_parsed_id(&id.into_inner(), |id| {
-        let zettel = vault.load_zettel(&id);
+        let source = match vault.load_zettel_source(&id) {
+            Some(source) => source,
+            None => return pages::error::generate_404(),
+        };
 
-        let vault_ref = vault.get_ref();
-
-        let conversion_context =
-            zettel::document::conversions::html::HtmlConversionContext::new(Arc::clone(vault_ref));
+        let title = vault
+            .title_of_entity(&id)
+            .unwrap_or_else(|| id.as_readable_string());
 
         let html = pages::decorate_maud_html(
-            "Zettel",
-            html! {
-                h1 { "Zettel" }
-                form action=(format!("{}", id.as_safe_uri())) method="post" {
-                    textarea name="content" {
-                        @if let Some(zettel) = zettel {
-                            (zettel.body_as_document().unwrap().as_html(&conversion_context))
-                        }
+            &title,
+            decorate_content_page(html! {
+                h1 { (title) }
+                form action=(id.as_safe_uri()) method="post" {
+                    textarea name="content" rows="30" style="width: 100%; font-family: monospace;" {
+                        (source)
                     }
+                    br;
                     button type="submit" { "Save" }
+                    " "
+                    a href=(id.as_safe_uri()) { "Cancel" }
                 }
-            },
+            }),
         );
 
         HttpResponse::Ok().body(html.into_string())
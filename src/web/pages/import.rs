@@ -0,0 +1,53 @@
+use actix_web::HttpResponse;
+use maud::html;
+
+use crate::{
+    core::{entity, vault::import},
+    web::pages::{self, decorate_content_page},
+};
+
+fn generate_report(report: &import::ImportReport) -> maud::PreEscaped<String> {
+    html! {
+        h2 { "Result" }
+        p { (report.summary()) }
+        @if !report.imported.is_empty() {
+            ul {
+                @for hash in &report.imported {
+                    @let id = entity::Id::Email(hash.clone());
+                    li { a href=(id.as_safe_uri()) { (id.as_readable_string()) } }
+                }
+            }
+        }
+        @if !report.failed.is_empty() {
+            h3 { "Failures" }
+            ul {
+                @for (source, reason) in &report.failed {
+                    li { code { (source) } ": " (reason) }
+                }
+            }
+        }
+    }
+}
+
+/// The form for importing emails from a path on the server, followed by the result of the last import.
+pub fn generate_import_page(report: Option<&import::ImportReport>) -> HttpResponse {
+    let html = pages::decorate_maud_html(
+        "Import emails",
+        decorate_content_page(html! {
+            h1 { "Import emails" }
+            p { "Import a single " code { ".eml" } " file, an mbox archive, a Maildir, or a directory containing any of these." }
+            form action="/import" method="post" {
+                input type="text" name="path" placeholder="/path/to/mail" style="width: 100%;";
+                br;
+                label { input type="checkbox" name="gzip"; " Store compressed (" code { ".eml.gz" } ")" }
+                br;
+                button type="submit" { "Import" }
+            }
+            @if let Some(report) = report {
+                (generate_report(report))
+            }
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
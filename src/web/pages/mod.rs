@@ -9,6 +9,10 @@ use crate::core::{
 
 pub mod error;
 pub mod content;
+pub mod import;
+pub mod search;
+pub mod tags;
+pub mod tasks;
 
 
 pub fn decorate_maud_html(title: &str, content: maud::PreEscaped<String>) -> maud::PreEscaped<String> {
@@ -60,6 +64,16 @@ pub fn generate_download_resource(resource: vault::resource::Resource, resource_
         .unwrap_or_else(|| "application/octet-stream");
     let content = resource.read_to_bytes(resource_interface).unwrap();
 
+    generate_download_bytes(mime, content)
+}
+
+pub fn generate_download_file(file: vault::resource::file::FileContent) -> HttpResponse {
+    let mime = file.metadata().mime_type().to_string();
+
+    generate_download_bytes(&mime, file.extract_content())
+}
+
+fn generate_download_bytes(mime: &str, content: Vec<u8>) -> HttpResponse {
     let mime = if mime == "text/plain" || mime == "text/markdown" {
         format!("text/plain; charset=utf-8")
     } else {
@@ -69,7 +83,34 @@ pub fn generate_download_resource(resource: vault::resource::Resource, resource_
     HttpResponse::Ok().content_type(mime).body(content)
 }
 
-pub fn generate_show_file(id: entity::Id, file: vault::resource::file::FileContent) -> HttpResponse {
+/// Generate the "Linked from" section listing all entities referring to the given entity.
+pub fn generate_backlinks_box(vault: &Arc<vault::Vault>, id: &entity::Id) -> maud::PreEscaped<String> {
+    let backlinks = vault.backlinks(id);
+
+    if backlinks.is_empty() {
+        return maud::PreEscaped(String::new());
+    }
+
+    html! {
+        div class="backlinks" {
+            h2 { "Linked from" }
+            ul {
+                @for backlink in &backlinks {
+                    li {
+                        a href=(backlink.source.as_safe_uri()) {
+                            (vault.title_of_entity(&backlink.source).unwrap_or_else(|| backlink.source.as_readable_string()))
+                        }
+                        @for context in &backlink.contexts {
+                            blockquote class="backlink-context" { (context) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn generate_show_file(vault: &Arc<vault::Vault>, id: entity::Id, file: vault::resource::file::FileContent) -> HttpResponse {
     let title = file
         .metadata()
         .title()
@@ -88,6 +129,7 @@ pub fn generate_show_file(id: entity::Id, file: vault::resource::file::FileConte
             h1 { (title) }
             p { "MIME type: " code { (mime) } }
             (displayed_content_html)
+            (generate_backlinks_box(vault, &id))
         },
     );
 
@@ -99,7 +141,8 @@ pub fn generate_show_entity(vault: &Arc<vault::Vault>, id: entity::Id) -> HttpRe
 
     match entity {
         Some(entity::Entity::Zettel(zettel)) => content::zettel::generate_show_zettel(vault, id, zettel),
-        Some(entity::Entity::File(file)) => generate_show_file(id, file),
+        Some(entity::Entity::File(file)) => generate_show_file(vault, id, file),
+        Some(entity::Entity::Email(email)) => content::email::generate_show_email(vault, id, email),
         _ => error::generate_404(),
     }
 }
@@ -109,16 +152,21 @@ pub async fn show_entity(
     id: web::Path<String>,
     header: web::Header<actix_web::http::header::Accept>,
 ) -> HttpResponse {
-    // If we accept HTML, we will show the entity. Otherwise, we will download it.
+    // If we accept HTML, we will show the entity. If we accept JSON, we describe it like the API does.
+    // Otherwise, we will download it.
 
-    let accept_html: bool = header
-        .iter()
-        .any(|accept| accept.to_string().to_lowercase().contains("text/html"));
+    let accepts = |mime_type: &str| {
+        header
+            .iter()
+            .any(|accept| accept.to_string().to_lowercase().contains(mime_type))
+    };
 
-    if accept_html {
+    if accepts("text/html") {
         generate_page_with_parsed_id(&id.into_inner(), |id| {
             generate_show_entity(&vault, id)
         })
+    } else if accepts("application/json") {
+        super::api::show_entity(vault, id, web::Query(Default::default())).await
     } else {
         super::routes::download_entity(vault, id).await // TODO: Move this to us!
     }
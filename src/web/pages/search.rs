@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use actix_web::HttpResponse;
+use maud::html;
+
+use crate::{
+    core::vault,
+    web::pages::{self, decorate_content_page},
+};
+
+pub fn generate_search_form(query: &str) -> maud::Markup {
+    html! {
+        form action="/search" method="get" class="search-form" {
+            input type="search" name="q" value=(query) placeholder="Search the vault" autofocus;
+            " "
+            button type="submit" { "Search" }
+        }
+    }
+}
+
+pub fn generate_search_page(vault: &Arc<vault::Vault>, query: &str) -> HttpResponse {
+    let hits = if query.trim().is_empty() {
+        vec![]
+    } else {
+        vault.search(query)
+    };
+
+    let title = if query.trim().is_empty() {
+        "Search".to_string()
+    } else {
+        format!("Search: {}", query)
+    };
+
+    let html = pages::decorate_maud_html(
+        &title,
+        decorate_content_page(html! {
+            p { a href="/" { "All entities" } }
+            (generate_search_form(query))
+            @if !query.trim().is_empty() {
+                p { (hits.len()) " results" }
+                ol class="search-results" {
+                    @for hit in &hits {
+                        li {
+                            a href=(hit.id.as_safe_uri()) { (hit.title) }
+                            @if !hit.aliases.is_empty() {
+                                " " span class="aliases" { "(" (hit.aliases.join(", ")) ")" }
+                            }
+                            @if let Some(snippet) = &hit.snippet {
+                                p class="search-snippet" { (snippet.as_html()) }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
+
+pub fn generate_search_json(vault: &Arc<vault::Vault>, query: &str) -> HttpResponse {
+    #[derive(serde::Serialize)]
+    struct Result<'a> {
+        #[serde(flatten)]
+        hit: &'a crate::search::SearchHit,
+        uri: String,
+    }
+
+    let hits = vault.search(query);
+
+    let results = hits
+        .iter()
+        .map(|hit| Result {
+            uri: hit.id.as_safe_uri(),
+            hit,
+        })
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok().json(results)
+}
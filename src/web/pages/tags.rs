@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use actix_web::HttpResponse;
+use maud::html;
+
+use crate::{
+    core::{entity::zettel::document::conversions::html::tag_uri, vault},
+    web::pages::{self, decorate_content_page},
+};
+
+/// Generate an overview of all tags in the vault. Nested tags are indented below their parents.
+pub fn generate_tag_overview(vault: &Arc<vault::Vault>) -> HttpResponse {
+    let tags = vault.tags();
+
+    let html = pages::decorate_maud_html(
+        "Tags",
+        decorate_content_page(html! {
+            h1 { "Tags" }
+            @if tags.is_empty() {
+                p { "There are no tags in this vault." }
+            }
+            ul class="tag-list" {
+                @for (tag, count) in &tags {
+                    li style=(format!("margin-left: {}em", 1.5 * tag.matches('/').count() as f32)) {
+                        a href=(tag_uri(tag)) { code { (format!("#{}", tag)) } }
+                        " (" (count) ")"
+                    }
+                }
+            }
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
+
+/// Generate a page listing all entities carrying a tag, including those carrying one of its nested tags.
+pub fn generate_tag_page(vault: &Arc<vault::Vault>, tag: &str) -> HttpResponse {
+    let tag = vault::caching::tags::normalize_tag(tag);
+
+    let mut entities = vault
+        .entities_with_tag(&tag)
+        .into_iter()
+        .map(|id| {
+            let title = vault
+                .title_of_entity(&id)
+                .unwrap_or_else(|| id.as_readable_string());
+            (id, title)
+        })
+        .collect::<Vec<_>>();
+
+    if entities.is_empty() {
+        return pages::error::generate_404();
+    }
+
+    entities.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let subtags = vault.subtags(&tag);
+    let title = format!("#{}", tag);
+
+    let parents = tag
+        .char_indices()
+        .filter(|(_, c)| *c == '/')
+        .map(|(index, _)| &tag[..index])
+        .collect::<Vec<_>>();
+
+    let html = pages::decorate_maud_html(
+        &title,
+        decorate_content_page(html! {
+            p {
+                a href="/tags" { "All tags" }
+                @for parent in &parents {
+                    " / "
+                    a href=(tag_uri(parent)) { code { (format!("#{}", parent)) } }
+                }
+            }
+            h1 { (title) }
+            @if !subtags.is_empty() {
+                h2 { "Nested tags" }
+                ul {
+                    @for (subtag, count) in &subtags {
+                        li {
+                            a href=(tag_uri(subtag)) { code { (format!("#{}", subtag)) } }
+                            " (" (count) ")"
+                        }
+                    }
+                }
+            }
+            h2 { "Entities" }
+            ul {
+                @for (id, title) in &entities {
+                    li {
+                        a href=(id.as_safe_uri()) { (title) }
+                    }
+                }
+            }
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
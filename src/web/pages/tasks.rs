@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use actix_web::HttpResponse;
+use maud::html;
+
+use crate::{
+    core::{
+        entity::zettel::document::block::bullet_point::Status,
+        vault::{self, tasks::TaskFilter},
+    },
+    web::pages::{self, decorate_content_page},
+};
+
+/// The form to choose which tasks are listed.
+fn generate_filter_form(show: &str, tag: &str) -> maud::Markup {
+    let options = [("open", "Open"), ("overdue", "Overdue"), ("all", "All")];
+
+    html! {
+        form action="/tasks" method="get" class="search-form" {
+            select name="show" {
+                @for (value, label) in options {
+                    @if value == show {
+                        option value=(value) selected { (label) }
+                    } @else {
+                        option value=(value) { (label) }
+                    }
+                }
+            }
+            " "
+            input type="text" name="tag" value=(tag) placeholder="Tag";
+            " "
+            button type="submit" { "Filter" }
+        }
+    }
+}
+
+/// Generate a list of the tasks in the vault. `show` is one of `open`, `overdue` and `all`.
+pub fn generate_tasks_page(vault: &Arc<vault::Vault>, show: &str, tag: &str) -> HttpResponse {
+    let filter = TaskFilter {
+        open: show != "all",
+        overdue: show == "overdue",
+        tag: Some(tag.trim()).filter(|tag| !tag.is_empty()).map(str::to_string),
+    };
+
+    let today = chrono::Local::now().date_naive();
+
+    let mut tasks = vault
+        .filtered_tasks(&filter)
+        .into_iter()
+        .map(|task| {
+            let title = vault
+                .title_of_entity(&task.entity)
+                .unwrap_or_else(|| task.entity.as_readable_string());
+            (task, title)
+        })
+        .collect::<Vec<_>>();
+
+    // Tasks that are due come first, the earliest at the top
+    tasks.sort_by(|(a, a_title), (b, b_title)| {
+        (a.info.due.is_none(), a.info.due, a_title).cmp(&(b.info.due.is_none(), b.info.due, b_title))
+    });
+
+    let html = pages::decorate_maud_html(
+        "Tasks",
+        decorate_content_page(html! {
+            p { a href="/" { "All entities" } }
+            h1 { "Tasks" }
+            (generate_filter_form(show, tag))
+            p { (tasks.len()) " tasks" }
+            ul class="task-list" {
+                @for (task, title) in &tasks {
+                    @let class_name = match task.info.status {
+                        Status::Pending => "task task-pending",
+                        Status::InProgress => "task task-in-progress",
+                        Status::Done => "task task-done",
+                        Status::Cancelled => "task task-cancelled",
+                    };
+                    li class=(class_name) {
+                        code { (format!("[{}]", task.info.status.as_char())) }
+                        " " (task.text) " "
+                        @if let Some(due) = task.info.due {
+                            @if task.info.is_overdue(today) {
+                                strong class="task-overdue" { "due " (due.format("%Y-%m-%d")) }
+                            } @else {
+                                span { "due " (due.format("%Y-%m-%d")) }
+                            }
+                            " "
+                        }
+                        "(" a href=(task.entity.as_safe_uri()) { (title) } ")"
+                    }
+                }
+            }
+        }),
+    );
+
+    HttpResponse::Ok().body(html.into_string())
+}
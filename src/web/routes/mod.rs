@@ -1,4 +1,4 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use maud::html;
 use std::{collections::HashMap, sync::Arc};
 
@@ -6,6 +6,32 @@ use crate::core::{entity, vault};
 
 use super::pages::{self, decorate_content_page};
 
+/// Whether a form was submitted from one of our own pages.
+///
+/// Browsers send the `Origin` of every cross-site form post, so another site can't make them import
+/// or overwrite anything. Clients that send no `Origin` at all aren't browsers and are trusted.
+fn is_same_origin(request: &HttpRequest) -> bool {
+    let origin = match request.headers().get(actix_web::http::header::ORIGIN) {
+        Some(origin) => origin,
+        None => return true,
+    };
+
+    let host = request.connection_info().host().to_string();
+
+    origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.split_once("://"))
+        .is_some_and(|(_, origin_host)| origin_host == host)
+}
+
+fn cross_origin_response() -> HttpResponse {
+    pages::error::generate_http_error_response(
+        actix_web::http::StatusCode::FORBIDDEN,
+        Some("Forms can only be submitted from Athena itself".to_string()),
+    )
+}
+
 pub async fn web_file(_vault: web::Data<Arc<vault::Vault>>, id: web::Path<String>) -> HttpResponse {
     let file_name = id.into_inner();
 
@@ -14,6 +40,10 @@ pub async fn web_file(_vault: web::Data<Arc<vault::Vault>>, id: web::Path<String
             let css = include_str!("../../../static/css.css");
             HttpResponse::Ok().content_type("text/css").body(css)
         }
+        "tasks.js" => {
+            let js = include_str!("../../../static/tasks.js");
+            HttpResponse::Ok().content_type("text/javascript").body(js)
+        }
         _ => pages::error::generate_404(),
     }
 }
@@ -35,6 +65,8 @@ pub async fn list_entities(vault: web::Data<Arc<vault::Vault>>) -> impl Responde
     let html = pages::decorate_maud_html(
         "Zettel",
         decorate_content_page(html! {
+            (pages::search::generate_search_form(""))
+            p { a href="/tags" { "Tags" } " " a href="/tasks" { "Tasks" } " " a href="/import" { "Import emails" } }
             ul {
                 @for (id, title) in zettels {
                     li {
@@ -48,6 +80,88 @@ pub async fn list_entities(vault: web::Data<Arc<vault::Vault>>) -> impl Responde
     HttpResponse::Ok().body(html.into_string())
 }
 
+pub async fn search(
+    vault: web::Data<Arc<vault::Vault>>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let q = query.get("q").map(|s| s.as_str()).unwrap_or("");
+
+    pages::search::generate_search_page(&vault, q)
+}
+
+pub async fn search_json(
+    vault: web::Data<Arc<vault::Vault>>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let q = query.get("q").map(|s| s.as_str()).unwrap_or("");
+
+    pages::search::generate_search_json(&vault, q)
+}
+
+pub async fn list_tags(vault: web::Data<Arc<vault::Vault>>) -> HttpResponse {
+    pages::tags::generate_tag_overview(&vault)
+}
+
+pub async fn show_tag(vault: web::Data<Arc<vault::Vault>>, tag: web::Path<String>) -> HttpResponse {
+    pages::tags::generate_tag_page(&vault, &tag.into_inner())
+}
+
+pub async fn list_tasks(
+    vault: web::Data<Arc<vault::Vault>>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let show = query.get("show").map(|s| s.as_str()).unwrap_or("open");
+    let tag = query.get("tag").map(|s| s.as_str()).unwrap_or("");
+
+    pages::tasks::generate_tasks_page(&vault, show, tag)
+}
+
+pub async fn show_thread(vault: web::Data<Arc<vault::Vault>>, id: web::Path<String>) -> HttpResponse {
+    pages::generate_page_with_parsed_id(&id.into_inner(), |id| {
+        pages::content::email::generate_thread_page(&vault, &id)
+    })
+}
+
+pub async fn show_import(_vault: web::Data<Arc<vault::Vault>>) -> HttpResponse {
+    pages::import::generate_import_page(None)
+}
+
+pub async fn post_import(
+    vault: web::Data<Arc<vault::Vault>>,
+    request: HttpRequest,
+    body: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    if !is_same_origin(&request) {
+        return cross_origin_response();
+    }
+
+    let path = match body.get("path").filter(|path| !path.trim().is_empty()) {
+        Some(path) => std::path::PathBuf::from(path.trim()),
+        None => {
+            return pages::error::generate_http_error_response(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                Some("Missing path".to_string()),
+            )
+        }
+    };
+
+    let options = vault::import::ImportOptions {
+        compress: body.contains_key("gzip"),
+    };
+
+    // Importing large archives takes a while, so keep it off the async workers
+    let vault = vault.into_inner();
+    let report = web::block(move || vault.import_emails(&path, &options)).await;
+
+    match report {
+        Ok(report) => pages::import::generate_import_page(Some(&report)),
+        Err(_) => pages::error::generate_http_error_response(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Some("Import failed".to_string()),
+        ),
+    }
+}
+
 pub async fn process_entity(
     vault: web::Data<Arc<vault::Vault>>,
     id: web::Path<String>,
@@ -69,6 +183,11 @@ pub async fn download_entity(
     pages::generate_page_with_parsed_id(&id.into_inner(), |id| {
         if let Some(resource) = vault.load_resource(&id) {
             pages::generate_download_resource(resource, vault.resource_interface())
+        } else if let entity::Id::Attachment(parent, index) = &id {
+            match vault.load_attachment(parent, *index) {
+                Some(file) => pages::generate_download_file(file),
+                None => pages::error::generate_404(),
+            }
         } else {
             pages::error::generate_http_error_response(
                 actix_web::http::StatusCode::NOT_IMPLEMENTED,
@@ -81,25 +200,63 @@ pub async fn download_entity(
 pub async fn post_entity(
     vault: web::Data<Arc<vault::Vault>>,
     id: web::Path<String>,
+    request: HttpRequest,
     body: web::Form<HashMap<String, String>>,
 ) -> HttpResponse {
-    pages::generate_page_with_parsed_id(&id.into_inner(), |id| {
-        let content = body.get("content").unwrap();
+    if !is_same_origin(&request) {
+        return cross_origin_response();
+    }
 
-        println!("Received content: {}", content);
+    pages::generate_page_with_parsed_id(&id.into_inner(), |id| {
+        let content = match body.get("content") {
+            Some(content) => content,
+            None => {
+                return pages::error::generate_http_error_response(
+                    actix_web::http::StatusCode::BAD_REQUEST,
+                    Some("Missing content".to_string()),
+                )
+            }
+        };
 
-        let zettel = vault.load_zettel(&id);
+        // Browsers submit textareas with CRLF line endings, but our files use LF
+        let content = content.replace("\r\n", "\n");
 
-        match zettel {
-            Some(_zettel) => {
-                println!("Zettel found, updating content");
-            }
-            None => {
-                println!("Zettel not found");
+        match vault.save_zettel_source(&id, &content) {
+            Ok(()) => {
+                // We are okay and we are returning the Zettel
+                pages::generate_show_entity(&vault, id)
             }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => pages::error::generate_404(),
+            Err(e) => pages::error::generate_http_error_response(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Some(format!("Unable to save: {}", e)),
+            ),
         }
-
-        // We are okay and we are returning the Zettel
-        pages::generate_show_entity(&vault, id)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_same_origin;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn forms_from_other_sites_are_refused() {
+        let request = |origin: Option<&str>| {
+            let request = TestRequest::post().insert_header(("Host", "localhost:8080"));
+
+            match origin {
+                Some(origin) => request.insert_header(("Origin", origin)),
+                None => request,
+            }
+            .to_http_request()
+        };
+
+        assert!(is_same_origin(&request(Some("http://localhost:8080"))));
+        assert!(is_same_origin(&request(None)));
+
+        assert!(!is_same_origin(&request(Some("https://evil.example"))));
+        assert!(!is_same_origin(&request(Some("http://localhost:8081"))));
+        assert!(!is_same_origin(&request(Some("null"))));
+    }
+}
@@ -0,0 +1,35 @@
+// Toggle tasks by clicking their checkboxes. The server only accepts the change if
+// the note hasn't been modified since the page was loaded.
+
+document.addEventListener("change", async (event) => {
+    const checkbox = event.target;
+
+    if (!checkbox.classList.contains("task-checkbox")) {
+        return;
+    }
+
+    const body = checkbox.closest(".zettel-body");
+
+    checkbox.disabled = true;
+
+    const response = await fetch(body.dataset.toggleUri, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+            line: Number(checkbox.dataset.line),
+            hash: body.dataset.hash,
+        }),
+    });
+
+    if (response.ok) {
+        const result = await response.json();
+
+        body.dataset.hash = result.hash;
+        body.innerHTML = result.html;
+    } else {
+        const result = await response.json().catch(() => ({ error: response.statusText }));
+
+        alert(result.error);
+        location.reload();
+    }
+});